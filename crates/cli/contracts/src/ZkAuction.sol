@@ -14,6 +14,36 @@ contract ZkAuction is IERC721Receiver {
     bytes32 public constant ELF_COMMITMENT = 0x1751d5d4ef537625091e623bbfd7cc457b0503daa18d06b59d5d0f13d38fdb5f;
     address public constant ALIGNED_SERVICE_MANAGER = 0x58F280BeBE9B34c9939C3C39e0890C81f163B623;
     address public constant ALIGNED_PAYMENT_SERVICE_ADDR = 0x815aeCA64a974297942D2Bbf034ABEe22a38A003;
+    // Layout version of the public values committed by the zkVM program
+    uint8 public constant PUBLIC_VALUES_VERSION = 8;
+    // Layout version of the auction data hashed in `calculateAuctionHash`
+    uint8 public constant AUCTION_DATA_VERSION = 11;
+    // Denominator of the fee and royalty basis points
+    uint16 public constant BPS_DENOMINATOR = 10000;
+
+    enum AuctionRule {
+        HighestBid, // The highest bids win
+        LowestUniqueBid // The lowest bids submitted by exactly one bidder win
+    }
+
+    enum AuctionDirection {
+        Forward, // Sale: the highest bids win, the reserve price is the minimum accepted price
+        Reverse // Procurement: the lowest bids win, the reserve price is the maximum accepted price
+    }
+
+    enum PricingRule {
+        FirstPrice, // Winners pay the lowest winning bid, their own bid when a single item is sold
+        SecondPrice // Winners pay the highest losing bid (Vickrey auction when a single item is sold)
+    }
+
+    enum TieBreak {
+        EarliestBid, // The earliest of the bids tied at the same amount wins
+        Random // A pseudo-random bid wins, seeded by the auction hash
+    }
+
+    enum CipherScheme {
+        Ecies // ECIES on secp256k1 with AES-256-GCM
+    }
 
     struct Auction {
         address owner; // Owner of the auction
@@ -21,10 +51,33 @@ contract ZkAuction is IERC721Receiver {
         IERC20 token; // Token used in the auction
         Asset asset; // Asset being auctioned
         Bid[] bids; // Array of bids placed on the auction
-        Winner winner; // Winner of the auction
+        address[] winners; // Winners of the auction, one per item
+        uint128 clearingPrice; // Price paid by every winner
         uint256 depositPrice; // Deposit price when bidder start bid
         uint256 endTime; // Time when the bid phase end
         bool ended; // Status of the auction
+        PricingRule pricingRule; // Rule deciding the price paid by the winner
+        uint128 reservePrice; // Minimum price accepted by the owner
+        TieBreak tieBreak; // Policy deciding the winner among tied bids
+        AuctionRule auctionRule; // Rule deciding which bids win
+        AuctionDirection direction; // Ordering of the bids, a reverse auction pays the winners
+        bool revealBids; // Whether every bid is revealed in the proof once the auction ends
+        CipherScheme cipherScheme; // Scheme used by the bidders to encrypt their bids
+        PayoutTerms payoutTerms; // Split of the proceeds of a sale
+        uint32 units; // Number of identical items, one per winner
+    }
+
+    struct PayoutTerms {
+        uint16 feeBps; // Protocol fee taken from the proceeds, in basis points
+        address feeRecipient; // Address receiving the protocol fee
+        uint16 royaltyBps; // Royalty taken from the proceeds, in basis points
+        address royaltyRecipient; // Address receiving the royalty
+    }
+
+    struct Payout {
+        uint256 ownerAmount; // Amount paid to the owner
+        uint256 feeAmount; // Amount paid to the fee recipient
+        uint256 royaltyAmount; // Amount paid to the royalty recipient
     }
 
     struct Bid {
@@ -36,12 +89,27 @@ contract ZkAuction is IERC721Receiver {
         string name; // Name of the asset
         string description; // Description of the asset
         address nftContract; // The nft to be auction
-        uint256 tokenId; // Id nft
+        uint256[] tokenIds; // Ids of the identical nfts, one per item sold
     }
 
-    struct Winner {
-        address winner; // Address of the winner
-        uint128 price; // Price submitted of winner
+    struct RevealedBid {
+        address bidder; // Address of the bidder
+        uint128 amount; // Decrypted amount, 0 if the bid is not valid
+        bool valid; // Whether the bid was decrypted and bound to the bidder and the auction
+    }
+
+    struct PublicValues {
+        uint8 version; // Layout version of the public values
+        bytes32 auctionHash; // Hash of the auction data
+        bytes32 encryptionKeyHash; // Hash of the owner's public key
+        bytes32 bidsRoot; // Merkle root of the bids, bound by the auction hash
+        address[] winners; // Addresses of the winners from the best ranked bid, empty if there is no winner
+        uint128[] winnerAmounts; // Amounts of the winning bids
+        uint128 clearingPrice; // Price paid by every winner
+        uint8 tieBreak; // Tie-break policy applied by the zkVM program
+        uint32[] rejectedBids; // Indices of the bids excluded from the auction
+        RevealedBid[] revealedBids; // Every bid in bid order if the auction reveals the bids
+        Payout payout; // Split of the proceeds computed by the zkVM program
     }
 
     uint256 public auctionCount; // Counter for auctions
@@ -57,6 +125,8 @@ contract ZkAuction is IERC721Receiver {
     event AuctionCreated(uint256 indexed auctionId, address indexed owner);
     event NewBid(uint256 indexed auctionId, address indexed bidder, bytes encryptedPrice);
     event AuctionEnded(uint256 indexed auctionId, address indexed winner, uint128 price);
+    // Merkle root of the bids committed by the proof, bidders check the inclusion of their bid in it
+    event BidsProven(uint256 indexed auctionId, bytes32 bidsRoot);
 
     modifier onlyOwner(uint256 auctionId) {
         Auction storage auction = auctions[auctionId];
@@ -74,18 +144,61 @@ contract ZkAuction is IERC721Receiver {
         bytes memory _encryptionKey,
         IERC20 _token,
         address _nftContract,
-        uint256 _tokenId,
+        uint256[] memory _tokenIds,
+        uint32 _units,
         string memory _assetName,
         string memory _assetDescription,
         uint256 _depositPrice,
-        uint256 _duration
+        uint256 _duration,
+        PricingRule _pricingRule,
+        uint128 _reservePrice,
+        TieBreak _tieBreak,
+        AuctionRule _auctionRule,
+        AuctionDirection _direction,
+        bool _revealBids,
+        CipherScheme _cipherScheme,
+        PayoutTerms memory _payoutTerms
     ) public {
         require(_depositPrice > 0, "Deposit price must be greater than zero");
+        // The zkVM program reads the deposit price as a uint128 to reject the bids above it
+        require(_depositPrice <= type(uint128).max, "Deposit price must fit in uint128");
+        if (_direction == AuctionDirection.Forward) {
+            require(_reservePrice <= _depositPrice, "Reserve price must not exceed deposit price");
+        } else {
+            require(_reservePrice > 0, "Maximum price must be greater than zero");
+            require(_auctionRule == AuctionRule.HighestBid, "Lowest unique bid auctions must be forward");
+        }
         require(_duration > 0, "Duration must be greater than zero");
+        if (_direction == AuctionDirection.Forward) {
+            require(_tokenIds.length > 0, "At least one NFT must be auctioned");
+            require(_units == _tokenIds.length, "Units must match the auctioned NFTs");
+        } else {
+            // The owner buys the items, there is no NFT to escrow
+            require(_units > 0, "At least one item must be procured");
+            require(_tokenIds.length == 0, "Reverse auctions do not escrow NFTs");
+        }
+        require(
+            _auctionRule == AuctionRule.HighestBid || _pricingRule == PricingRule.FirstPrice,
+            "Lowest unique bid auctions must be first-price"
+        );
+        require(
+            _payoutTerms.feeBps + _payoutTerms.royaltyBps <= BPS_DENOMINATOR,
+            "Fee and royalty must not exceed the proceeds"
+        );
+        require(
+            _direction == AuctionDirection.Forward || (_payoutTerms.feeBps == 0 && _payoutTerms.royaltyBps == 0),
+            "Reverse auctions have no proceeds to split"
+        );
+        require(_payoutTerms.feeBps == 0 || _payoutTerms.feeRecipient != address(0), "Missing fee recipient");
+        require(
+            _payoutTerms.royaltyBps == 0 || _payoutTerms.royaltyRecipient != address(0), "Missing royalty recipient"
+        );
 
         IERC721 nftContract = IERC721(_nftContract);
-        require(nftContract.ownerOf(_tokenId) == msg.sender, "You must own the NFT to auction it");
-        require(nftContract.getApproved(_tokenId) == address(this), "You need approve the NFT to contract");
+        for (uint256 i = 0; i < _tokenIds.length; ++i) {
+            require(nftContract.ownerOf(_tokenIds[i]) == msg.sender, "You must own the NFT to auction it");
+            require(nftContract.getApproved(_tokenIds[i]) == address(this), "You need approve the NFT to contract");
+        }
 
         // Create auction
         auctionCount++;
@@ -93,19 +206,39 @@ contract ZkAuction is IERC721Receiver {
 
         newAuction.owner = msg.sender;
         newAuction.encryptionKey = _encryptionKey;
-        newAuction.asset = Asset(_assetName, _assetDescription, _nftContract, _tokenId);
+        newAuction.asset = Asset(_assetName, _assetDescription, _nftContract, _tokenIds);
         newAuction.depositPrice = _depositPrice;
         newAuction.endTime = block.timestamp + _duration; // Set auction end time
         newAuction.ended = false;
         newAuction.token = _token;
+        newAuction.pricingRule = _pricingRule;
+        newAuction.reservePrice = _reservePrice;
+        newAuction.tieBreak = _tieBreak;
+        newAuction.auctionRule = _auctionRule;
+        newAuction.direction = _direction;
+        newAuction.revealBids = _revealBids;
+        newAuction.cipherScheme = _cipherScheme;
+        newAuction.payoutTerms = _payoutTerms;
+        newAuction.units = _units;
 
         auctionsByOwner[msg.sender].push(newAuction);
 
-        // Deposit nft
-        nftContract.safeTransferFrom(msg.sender, address(this), _tokenId); // Transfer NFT to contract
+        // Deposit nfts
+        for (uint256 i = 0; i < _tokenIds.length; ++i) {
+            nftContract.safeTransferFrom(msg.sender, address(this), _tokenIds[i]); // Transfer NFT to contract
+        }
+        if (_direction == AuctionDirection.Reverse) {
+            // Escrow the maximum payment of every item
+            _token.safeTransferFrom(msg.sender, address(this), uint256(_reservePrice) * _units);
+        }
         emit AuctionCreated(auctionCount, msg.sender);
     }
 
+    // Get the winners of an auction
+    function getWinners(uint256 auctionId) public view returns (address[] memory) {
+        return auctions[auctionId].winners;
+    }
+
     // Get auctions for an owner
     function getAuctionsByOwner(address owner) public view returns (Auction[] memory) {
         return auctionsByOwner[owner];
@@ -131,39 +264,116 @@ contract ZkAuction is IERC721Receiver {
 
     /**
      * @notice Gets list bidders after the bid phase end
-     * @dev Uses auctionId to get list bidders.
+     * @dev Uses auctionId to get list bidders. The bids stay readable once the auction is finalized,
+     * so bidders can prove the inclusion of their bid in the proven set.
      */
     function getBids(uint256 auctionId) public view returns (Bid[] memory) {
         require(block.timestamp >= auctions[auctionId].endTime, "Auction has not ended yet");
-        require(!auctions[auctionId].ended, "Auction has ended");
         return auctions[auctionId].bids;
     }
 
     /**
-     * @notice Reveals the winner after the auction ends.
-     * @dev Uses a ZK-proof to reveal the highest valid bid.
+     * @notice Reveals the winners after the auction ends.
+     * @dev Uses a ZK-proof to reveal the best ranked valid bids under the auction rule, one per item, all paying the clearing price.
+     * Items without a winner go back to the owner. If no valid bid reaches the reserve price,
+     * the list of winners is empty: every NFT goes back to the owner and every bidder can withdraw their deposit.
+     * In a reverse auction, no NFT is escrowed: every winner is paid the clearing price from the escrow and the rest goes back to the owner.
+     * In a sale, the proceeds are split between the owner, the fee recipient and the royalty recipient as proven by the zkVM program.
      */
-    function finalizeAuction(uint256 auctionId, Winner memory _winner, bytes memory proof) public onlyOwner(auctionId) {
+    function finalizeAuction(
+        uint256 auctionId,
+        address[] memory _winners,
+        uint128 _price,
+        Payout memory _payout,
+        bytes memory proof
+    ) public onlyOwner(auctionId) {
         Auction storage auction = auctions[auctionId];
         require(auction.owner == msg.sender, "You need owner of auction");
         require(block.timestamp >= auctions[auctionId].endTime, "Auction has not ended yet");
         require(!auction.ended, "Auction has ended");
-        _verifyProof(_winner, auctionId, proof);
-        require(_winner.price <= auction.depositPrice, "Winner has more bid price than deposit price");
-        // Set winner and status auction
-        auction.winner = _winner;
+        bytes32 bidsRoot = _verifyProof(_winners, _price, _payout, auctionId, proof);
+        emit BidsProven(auctionId, bidsRoot);
+        if (auction.direction == AuctionDirection.Forward) {
+            require(_price <= auction.depositPrice, "Winner has more bid price than deposit price");
+        } else {
+            require(_price <= auction.reservePrice, "Winner has more bid price than maximum price");
+        }
+        require(_winners.length <= auction.units, "More winners than items");
+        // Set winners and status auction
+        auction.winners = _winners;
+        auction.clearingPrice = _price;
         auction.ended = true;
-        // Send nft
+        if (auction.direction == AuctionDirection.Reverse) {
+            _settleReverseAuction(auctionId, _winners, _price);
+            return;
+        }
         IERC721 nftContract = IERC721(auction.asset.nftContract);
-        nftContract.safeTransferFrom(address(this), auction.winner.winner, auction.asset.tokenId);
-        // Refund token
-        if (auction.depositPrice > auction.winner.price) {
-            auction.token.safeTransfer(auction.winner.winner, auction.depositPrice - auction.winner.price);
+        for (uint256 i = 0; i < auction.asset.tokenIds.length; ++i) {
+            // Send nft, unsold items go back to the owner
+            address receiver = i < _winners.length ? _winners[i] : auction.owner;
+            nftContract.safeTransferFrom(address(this), receiver, auction.asset.tokenIds[i]);
+        }
+        if (_winners.length == 0) {
+            // No sale
+            emit AuctionEnded(auctionId, address(0), 0);
+            return;
         }
+        for (uint256 i = 0; i < _winners.length; ++i) {
+            // Refund token
+            if (auction.depositPrice > _price) {
+                auction.token.safeTransfer(_winners[i], auction.depositPrice - _price);
+            }
+            emit AuctionEnded(auctionId, _winners[i], _price);
+        }
+        require(
+            _payout.ownerAmount + _payout.feeAmount + _payout.royaltyAmount == uint256(_price) * _winners.length,
+            "Payout does not match the proceeds"
+        );
         // Withdraw token
-        auction.token.safeTransfer(msg.sender, auction.winner.price);
+        auction.token.safeTransfer(msg.sender, _payout.ownerAmount);
+        if (_payout.feeAmount > 0) {
+            auction.token.safeTransfer(auction.payoutTerms.feeRecipient, _payout.feeAmount);
+        }
+        if (_payout.royaltyAmount > 0) {
+            auction.token.safeTransfer(auction.payoutTerms.royaltyRecipient, _payout.royaltyAmount);
+        }
+    }
+
+    // Pay the winners of a reverse auction from the escrow and return the rest to the owner
+    function _settleReverseAuction(uint256 auctionId, address[] memory _winners, uint128 _price) internal {
+        Auction storage auction = auctions[auctionId];
+        if (_winners.length == 0) {
+            emit AuctionEnded(auctionId, address(0), 0);
+        }
+        for (uint256 i = 0; i < _winners.length; ++i) {
+            auction.token.safeTransfer(_winners[i], _price);
+            emit AuctionEnded(auctionId, _winners[i], _price);
+        }
+        auction.token.safeTransfer(
+            auction.owner, uint256(auction.reservePrice) * auction.units - uint256(_price) * _winners.length
+        );
+    }
+
+    /**
+     * @notice Ends an auction that received no bid.
+     * @dev No proof is needed since there is no bid to decrypt, the NFT goes back to the owner.
+     */
+    function reclaimAuction(uint256 auctionId) public onlyOwner(auctionId) {
+        Auction storage auction = auctions[auctionId];
+        require(block.timestamp >= auction.endTime, "Auction has not ended yet");
+        require(!auction.ended, "Auction has ended");
+        require(auction.bids.length == 0, "Auction has bids");
+        auction.ended = true;
+        // Return nfts to the owner
+        for (uint256 i = 0; i < auction.asset.tokenIds.length; ++i) {
+            IERC721(auction.asset.nftContract).safeTransferFrom(address(this), auction.owner, auction.asset.tokenIds[i]);
+        }
+        if (auction.direction == AuctionDirection.Reverse) {
+            // Return the escrowed payment
+            auction.token.safeTransfer(auction.owner, uint256(auction.reservePrice) * auction.units);
+        }
 
-        emit AuctionEnded(auctionId, auction.winner.winner, auction.winner.price);
+        emit AuctionEnded(auctionId, address(0), 0);
     }
 
     function withdraw(uint256 auctionId) public {
@@ -175,10 +385,12 @@ contract ZkAuction is IERC721Receiver {
     }
 
     function _verifyProof(
-        Winner memory winner,
+        address[] memory winners,
+        uint128 price,
+        Payout memory payout,
         uint256 auctionId,
         bytes memory verifiedProofData
-    ) internal view {
+    ) internal view returns (bytes32) {
         (
             bytes memory publicInput,
             bytes32 proofCommitment,
@@ -201,11 +413,31 @@ contract ZkAuction is IERC721Receiver {
             "Invalid public input"
         );
 
-        (bytes32 auctionHash, address winner_addr, uint128 winner_price) = decodePublicInput(publicInput);
+        // One proof can finalize several auctions, find the outcome of this one
+        PublicValues[] memory batch = abi.decode(publicInput, (PublicValues[]));
+        bytes32 auctionHash = calculateAuctionHash(auctionId);
+        uint256 index = 0;
+        while (index < batch.length && batch[index].auctionHash != auctionHash) {
+            index++;
+        }
+        require(index < batch.length, "Auction is not in the proof");
+        PublicValues memory publicValues = batch[index];
 
-        require(winner_addr == winner.winner, "Winner in proof does not match");
-        require(winner_price == winner.price, "Winner in proof does not match");
-        require(calculateAuctionHash(auctionId) == auctionHash, "Auction hash does not match");
+        require(publicValues.version == PUBLIC_VALUES_VERSION, "Unsupported public values version");
+        require(
+            keccak256(abi.encode(publicValues.winners)) == keccak256(abi.encode(winners)),
+            "Winners in proof do not match"
+        );
+        require(publicValues.clearingPrice == price, "Clearing price in proof does not match");
+        require(
+            keccak256(abi.encode(publicValues.payout)) == keccak256(abi.encode(payout)),
+            "Payout in proof does not match"
+        );
+        require(publicValues.tieBreak == uint8(auctions[auctionId].tieBreak), "Tie-break in proof does not match");
+        require(
+            keccak256(auctions[auctionId].encryptionKey) == publicValues.encryptionKeyHash,
+            "Encryption key does not match"
+        );
 
         (
             bool callWasSuccessful,
@@ -228,40 +460,56 @@ contract ZkAuction is IERC721Receiver {
 
         bool proofIsIncludedBool = abi.decode(proofIsIncluded, (bool));
         require(proofIsIncludedBool, "proof not included in batch");
+        return publicValues.bidsRoot;
     }
 
     function calculateAuctionHash(uint256 auctionId) view internal returns (bytes32) {
-        Bid[] memory bids = auctions[auctionId].bids;
-        bytes memory hashInput = abi.encodePacked(auctionId);
-        for (uint256 i = 0; i < bids.length; ++i) {
-            hashInput = abi.encodePacked(hashInput, bids[i].bidder, bids[i].encryptedPrice);
-        }
+        // Domain separation: a proof is only valid for this chain and this contract
+        bytes memory hashInput = abi.encodePacked(
+            AUCTION_DATA_VERSION,
+            uint64(block.chainid),
+            address(this),
+            auctionId,
+            uint8(auctions[auctionId].pricingRule),
+            auctions[auctionId].reservePrice,
+            uint8(auctions[auctionId].tieBreak),
+            auctions[auctionId].units,
+            uint8(auctions[auctionId].auctionRule),
+            uint8(auctions[auctionId].direction),
+            uint128(auctions[auctionId].depositPrice),
+            auctions[auctionId].revealBids,
+            uint8(auctions[auctionId].cipherScheme),
+            auctions[auctionId].payoutTerms.feeBps,
+            auctions[auctionId].payoutTerms.feeRecipient,
+            auctions[auctionId].payoutTerms.royaltyBps,
+            auctions[auctionId].payoutTerms.royaltyRecipient,
+            calculateBidsRoot(auctions[auctionId].bids)
+        );
         return keccak256(hashInput);
     }
 
-    function decodePublicInput(bytes memory data) internal pure returns (bytes32 auctionHash, address winner_addr, uint128 winner_price) {
-        auctionHash = bytes32(slice(data, 0, 32));
-        winner_addr = address(bytes20(slice(data, 32 + 8, 20)));
-        winner_price = uint128(bytes16(reverse(slice(data, 32 + 8 + 20, 16))));
-    }
-
-    function slice(bytes memory data, uint256 start, uint256 length) internal pure returns (bytes memory) {
-        require(start + length <= data.length, "Slice out of bounds");
-
-        bytes memory result = new bytes(length);
-        for (uint256 i = 0; i < length; i++) {
-            result[i] = data[start + i];
+    // Merkle root of the bids: leaves are keccak256(0 || bidder || encryptedPrice), pairs are hashed
+    // by position as keccak256(1 || left || right), binding the bid order, and the last node of an
+    // odd level is promoted
+    function calculateBidsRoot(Bid[] memory bids) internal pure returns (bytes32) {
+        if (bids.length == 0) {
+            return bytes32(0);
         }
-
-        return result;
-    }
-
-    function reverse(bytes memory data) public pure returns (bytes memory) {
-        bytes memory result = new bytes(data.length);
-        for (uint256 i = 0; i < data.length; i++) {
-            result[i] = data[data.length - 1 - i];
+        bytes32[] memory nodes = new bytes32[](bids.length);
+        for (uint256 i = 0; i < bids.length; ++i) {
+            nodes[i] = keccak256(abi.encodePacked(uint8(0), bids[i].bidder, bids[i].encryptedPrice));
+        }
+        uint256 length = bids.length;
+        while (length > 1) {
+            for (uint256 i = 0; i < length / 2; ++i) {
+                nodes[i] = keccak256(abi.encodePacked(uint8(1), nodes[2 * i], nodes[2 * i + 1]));
+            }
+            if (length % 2 == 1) {
+                nodes[length / 2] = nodes[length - 1];
+            }
+            length = (length + 1) / 2;
         }
-        return result;
+        return nodes[0];
     }
 
     function onERC721Received(
@@ -47,10 +47,19 @@ contract testZkAuction is Test {
     function createAuction() public {
         vm.prank(owner);
         zk_auction.createAuction(
-            ownerPublicKey, IERC20(address(mock_token)), address(mock_nft), tokenId_1, assetName, assetDescription, depositPrice, duration
+            ownerPublicKey, IERC20(address(mock_token)), address(mock_nft), tokenIds(tokenId_1), 1, assetName, assetDescription, depositPrice, duration, ZkAuction.PricingRule.FirstPrice, 0, ZkAuction.TieBreak.EarliestBid, ZkAuction.AuctionRule.HighestBid, ZkAuction.AuctionDirection.Forward, false, ZkAuction.CipherScheme.Ecies, noPayoutTerms()
         );
     }
 
+    function tokenIds(uint256 tokenId) internal pure returns (uint256[] memory ids) {
+        ids = new uint256[](1);
+        ids[0] = tokenId;
+    }
+
+    function noPayoutTerms() internal pure returns (ZkAuction.PayoutTerms memory) {
+        return ZkAuction.PayoutTerms(0, address(0), 0, address(0));
+    }
+
     function create_new_bid() public {
         // Mint token to the bidder for testing
         vm.prank(bidder);
@@ -71,7 +80,7 @@ contract testZkAuction is Test {
         assertEq(mock_nft.ownerOf(tokenId_1), address(zk_auction));
         assertEq(zk_auction.auctionCount(), 1);
 
-        (address address_owner, bytes memory pk,,,, uint256 deposit_price, uint256 endTime, bool ended) =
+        (address address_owner, bytes memory pk,,,, uint256 deposit_price, uint256 endTime, bool ended,,,,,,,,,) =
                             zk_auction.auctions(0);
         assertEq(address_owner, owner);
         assertEq(pk, ownerPublicKey);
@@ -83,14 +92,22 @@ contract testZkAuction is Test {
     function testCreateAuctionDepositPriceZero() public {
         vm.prank(owner);
         vm.expectRevert(bytes("Deposit price must be greater than zero"));
-        zk_auction.createAuction(ownerPublicKey, IERC20(address(mock_token)), address(mock_nft), tokenId_1, assetName, assetDescription, 0, duration);
+        zk_auction.createAuction(ownerPublicKey, IERC20(address(mock_token)), address(mock_nft), tokenIds(tokenId_1), 1, assetName, assetDescription, 0, duration, ZkAuction.PricingRule.FirstPrice, 0, ZkAuction.TieBreak.EarliestBid, ZkAuction.AuctionRule.HighestBid, ZkAuction.AuctionDirection.Forward, false, ZkAuction.CipherScheme.Ecies, noPayoutTerms());
+    }
+
+    function testCreateAuctionDepositPriceTooLarge() public {
+        vm.prank(owner);
+        vm.expectRevert(bytes("Deposit price must fit in uint128"));
+        zk_auction.createAuction(
+            ownerPublicKey, IERC20(address(mock_token)), address(mock_nft), tokenIds(tokenId_1), 1, assetName, assetDescription, uint256(type(uint128).max) + 1, duration, ZkAuction.PricingRule.FirstPrice, 0, ZkAuction.TieBreak.EarliestBid, ZkAuction.AuctionRule.HighestBid, ZkAuction.AuctionDirection.Forward, false, ZkAuction.CipherScheme.Ecies, noPayoutTerms()
+        );
     }
 
     function testCreateAuctionDurationZero() public {
         vm.prank(owner);
         vm.expectRevert(bytes("Duration must be greater than zero"));
         zk_auction.createAuction(
-            ownerPublicKey, IERC20(address(mock_token)), address(mock_nft), tokenId_1, assetName, assetDescription, depositPrice, 0
+            ownerPublicKey, IERC20(address(mock_token)), address(mock_nft), tokenIds(tokenId_1), 1, assetName, assetDescription, depositPrice, 0, ZkAuction.PricingRule.FirstPrice, 0, ZkAuction.TieBreak.EarliestBid, ZkAuction.AuctionRule.HighestBid, ZkAuction.AuctionDirection.Forward, false, ZkAuction.CipherScheme.Ecies, noPayoutTerms()
         );
     }
 
@@ -98,7 +115,7 @@ contract testZkAuction is Test {
         vm.prank(bidder);
         vm.expectRevert(bytes("You must own the NFT to auction it"));
         zk_auction.createAuction(
-            ownerPublicKey, IERC20(address(mock_token)), address(mock_nft), tokenId_1, assetName, assetDescription, depositPrice, duration
+            ownerPublicKey, IERC20(address(mock_token)), address(mock_nft), tokenIds(tokenId_1), 1, assetName, assetDescription, depositPrice, duration, ZkAuction.PricingRule.FirstPrice, 0, ZkAuction.TieBreak.EarliestBid, ZkAuction.AuctionRule.HighestBid, ZkAuction.AuctionDirection.Forward, false, ZkAuction.CipherScheme.Ecies, noPayoutTerms()
         );
     }
 
@@ -106,7 +123,7 @@ contract testZkAuction is Test {
         vm.prank(owner);
         vm.expectRevert(bytes("You need approve the NFT to contract"));
         zk_auction.createAuction(
-            ownerPublicKey, IERC20(address(mock_token)), address(mock_nft), tokenId_2, assetName, assetDescription, depositPrice, duration
+            ownerPublicKey, IERC20(address(mock_token)), address(mock_nft), tokenIds(tokenId_2), 1, assetName, assetDescription, depositPrice, duration, ZkAuction.PricingRule.FirstPrice, 0, ZkAuction.TieBreak.EarliestBid, ZkAuction.AuctionRule.HighestBid, ZkAuction.AuctionDirection.Forward, false, ZkAuction.CipherScheme.Ecies, noPayoutTerms()
         );
     }
 
@@ -114,7 +131,7 @@ contract testZkAuction is Test {
     function testCreateBidAuctionEnded() public {
         createAuction();
 
-        (,,,,,, uint256 endTime, bool ended) = zk_auction.auctions(0);
+        (,,,,,, uint256 endTime, bool ended,,,,,,,,,) = zk_auction.auctions(0);
 
         assertTrue(block.timestamp < endTime);
         assertFalse(ended);
@@ -142,6 +159,133 @@ contract testZkAuction is Test {
         zk_auction.placeBid(0, encryptedPrice);
     }
 
+    function testCreateMultiUnitAuction() public {
+        vm.prank(owner);
+        mock_nft.approve(address(zk_auction), tokenId_2);
+        uint256[] memory ids = new uint256[](2);
+        ids[0] = tokenId_1;
+        ids[1] = tokenId_2;
+
+        vm.prank(owner);
+        zk_auction.createAuction(
+            ownerPublicKey, IERC20(address(mock_token)), address(mock_nft), ids, 2, assetName, assetDescription, depositPrice, duration, ZkAuction.PricingRule.SecondPrice, 0, ZkAuction.TieBreak.EarliestBid, ZkAuction.AuctionRule.HighestBid, ZkAuction.AuctionDirection.Forward, false, ZkAuction.CipherScheme.Ecies, noPayoutTerms()
+        );
+
+        // Ensure every NFT has been transferred to the auction contract
+        assertEq(mock_nft.ownerOf(tokenId_1), address(zk_auction));
+        assertEq(mock_nft.ownerOf(tokenId_2), address(zk_auction));
+
+        // Every NFT goes back to the owner without bids
+        uint256 auctionId = zk_auction.auctionCount();
+        vm.warp(block.timestamp + duration);
+        vm.prank(owner);
+        zk_auction.reclaimAuction(auctionId);
+        assertEq(mock_nft.ownerOf(tokenId_1), owner);
+        assertEq(mock_nft.ownerOf(tokenId_2), owner);
+    }
+
+    function testCreateLowestUniqueBidAuctionSecondPrice() public {
+        vm.prank(owner);
+        vm.expectRevert(bytes("Lowest unique bid auctions must be first-price"));
+        zk_auction.createAuction(
+            ownerPublicKey, IERC20(address(mock_token)), address(mock_nft), tokenIds(tokenId_1), 1, assetName, assetDescription, depositPrice, duration, ZkAuction.PricingRule.SecondPrice, 0, ZkAuction.TieBreak.EarliestBid, ZkAuction.AuctionRule.LowestUniqueBid, ZkAuction.AuctionDirection.Forward, false, ZkAuction.CipherScheme.Ecies, noPayoutTerms()
+        );
+    }
+
+    function testCreateReverseAuctionEscrow() public {
+        uint128 maximumPrice = depositPrice / 2;
+        vm.prank(owner);
+        mock_token.mint(owner, maximumPrice * 2);
+        vm.prank(owner);
+        mock_token.approve(address(zk_auction), maximumPrice * 2);
+
+        vm.prank(owner);
+        zk_auction.createAuction(
+            ownerPublicKey, IERC20(address(mock_token)), address(mock_nft), new uint256[](0), 2, assetName, assetDescription, depositPrice, duration, ZkAuction.PricingRule.FirstPrice, maximumPrice, ZkAuction.TieBreak.EarliestBid, ZkAuction.AuctionRule.HighestBid, ZkAuction.AuctionDirection.Reverse, false, ZkAuction.CipherScheme.Ecies, noPayoutTerms()
+        );
+        // Ensure the maximum payment of every item has been escrowed, and no NFT
+        assertEq(mock_token.balanceOf(address(zk_auction)), maximumPrice * 2);
+        assertEq(mock_nft.ownerOf(tokenId_1), owner);
+
+        // The escrow goes back to the owner without bids
+        uint256 auctionId = zk_auction.auctionCount();
+        vm.warp(block.timestamp + duration);
+        vm.prank(owner);
+        zk_auction.reclaimAuction(auctionId);
+        assertEq(mock_token.balanceOf(owner), maximumPrice * 2);
+    }
+
+    function testCreateReverseAuctionWithNFT() public {
+        vm.prank(owner);
+        vm.expectRevert(bytes("Reverse auctions do not escrow NFTs"));
+        zk_auction.createAuction(
+            ownerPublicKey, IERC20(address(mock_token)), address(mock_nft), tokenIds(tokenId_1), 1, assetName, assetDescription, depositPrice, duration, ZkAuction.PricingRule.FirstPrice, depositPrice, ZkAuction.TieBreak.EarliestBid, ZkAuction.AuctionRule.HighestBid, ZkAuction.AuctionDirection.Reverse, false, ZkAuction.CipherScheme.Ecies, noPayoutTerms()
+        );
+    }
+
+    function testCreateAuctionUnitsMismatch() public {
+        vm.prank(owner);
+        vm.expectRevert(bytes("Units must match the auctioned NFTs"));
+        zk_auction.createAuction(
+            ownerPublicKey, IERC20(address(mock_token)), address(mock_nft), tokenIds(tokenId_1), 2, assetName, assetDescription, depositPrice, duration, ZkAuction.PricingRule.FirstPrice, 0, ZkAuction.TieBreak.EarliestBid, ZkAuction.AuctionRule.HighestBid, ZkAuction.AuctionDirection.Forward, false, ZkAuction.CipherScheme.Ecies, noPayoutTerms()
+        );
+    }
+
+    function testCreateReverseAuctionMaximumPriceZero() public {
+        vm.prank(owner);
+        vm.expectRevert(bytes("Maximum price must be greater than zero"));
+        zk_auction.createAuction(
+            ownerPublicKey, IERC20(address(mock_token)), address(mock_nft), new uint256[](0), 1, assetName, assetDescription, depositPrice, duration, ZkAuction.PricingRule.FirstPrice, 0, ZkAuction.TieBreak.EarliestBid, ZkAuction.AuctionRule.HighestBid, ZkAuction.AuctionDirection.Reverse, false, ZkAuction.CipherScheme.Ecies, noPayoutTerms()
+        );
+    }
+
+    // Test reclaim phase
+    function testReclaimAuctionWithoutBids() public {
+        createAuction();
+        uint256 auctionId = zk_auction.auctionCount();
+
+        vm.warp(block.timestamp + duration);
+        vm.prank(owner);
+        zk_auction.reclaimAuction(auctionId);
+
+        assertEq(mock_nft.ownerOf(tokenId_1), owner);
+        (,,,,,,, bool ended,,,,,,,,,) = zk_auction.auctions(auctionId);
+        assertTrue(ended);
+    }
+
+    function testReclaimAuctionNotEnded() public {
+        createAuction();
+        uint256 auctionId = zk_auction.auctionCount();
+
+        vm.prank(owner);
+        vm.expectRevert(bytes("Auction has not ended yet"));
+        zk_auction.reclaimAuction(auctionId);
+    }
+
     // Test verify phase
     // Test follow time logic
+
+    function testCreateAuctionPayoutAboveProceeds() public {
+        vm.prank(owner);
+        vm.expectRevert(bytes("Fee and royalty must not exceed the proceeds"));
+        zk_auction.createAuction(
+            ownerPublicKey, IERC20(address(mock_token)), address(mock_nft), tokenIds(tokenId_1), 1, assetName, assetDescription, depositPrice, duration, ZkAuction.PricingRule.FirstPrice, 0, ZkAuction.TieBreak.EarliestBid, ZkAuction.AuctionRule.HighestBid, ZkAuction.AuctionDirection.Forward, false, ZkAuction.CipherScheme.Ecies, ZkAuction.PayoutTerms(6000, bidder, 4001, owner)
+        );
+    }
+
+    function testCreateAuctionFeeWithoutRecipient() public {
+        vm.prank(owner);
+        vm.expectRevert(bytes("Missing fee recipient"));
+        zk_auction.createAuction(
+            ownerPublicKey, IERC20(address(mock_token)), address(mock_nft), tokenIds(tokenId_1), 1, assetName, assetDescription, depositPrice, duration, ZkAuction.PricingRule.FirstPrice, 0, ZkAuction.TieBreak.EarliestBid, ZkAuction.AuctionRule.HighestBid, ZkAuction.AuctionDirection.Forward, false, ZkAuction.CipherScheme.Ecies, ZkAuction.PayoutTerms(250, address(0), 0, address(0))
+        );
+    }
+
+    function testCreateReverseAuctionWithFee() public {
+        vm.prank(owner);
+        vm.expectRevert(bytes("Reverse auctions have no proceeds to split"));
+        zk_auction.createAuction(
+            ownerPublicKey, IERC20(address(mock_token)), address(mock_nft), new uint256[](0), 1, assetName, assetDescription, depositPrice, duration, ZkAuction.PricingRule.FirstPrice, depositPrice, ZkAuction.TieBreak.EarliestBid, ZkAuction.AuctionRule.HighestBid, ZkAuction.AuctionDirection.Reverse, false, ZkAuction.CipherScheme.Ecies, ZkAuction.PayoutTerms(250, bidder, 0, address(0))
+        );
+    }
 }
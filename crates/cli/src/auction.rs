@@ -1,5 +1,11 @@
+use std::fs;
+
 use aligned_sdk::core::types::Network;
-use aligned_sp1_prover::{AuctionData, Bidder};
+use aligned_sp1_prover::{
+    bid_leaf, inclusion_proof, verify_inclusion, AuctionData, AuctionDirection,
+    AuctionPublicValues, AuctionRule, Bidder, CipherScheme, PricingRule, TieBreak,
+    AUCTION_DATA_VERSION,
+};
 use anyhow::{anyhow, Context, Result};
 use chrono::{TimeZone, Utc};
 use ecies::PublicKey;
@@ -8,7 +14,7 @@ use ethers::core::k256::ecdsa::SigningKey;
 use ethers::prelude::*;
 use ethers::types::{Address, Bytes, U256};
 use ethers::utils::keccak256;
-use prover_sdk::{encrypt_bidder_amount, get_winner_and_submit_proof};
+use prover_sdk::{encrypt_bidder_amount, get_winners_and_submit_proof, ArtifactDir, KeyStore};
 
 use crate::types::EthSigner;
 
@@ -27,9 +33,18 @@ abigen!(zkAuctionContract, "./assets/ZkAuction.json");
 /// * `name` - A string containing the name of the auction.
 /// * `description` - A string describing the auction.
 /// * `nft_contract_address` - The address of the ERC-721 contract managing the NFT to be auctioned.
-/// * `token_id` - ID of the NFT token to be auctioned.
+/// * `token_ids` - IDs of the identical NFT tokens to be auctioned, one per unit sold. Empty in a reverse auction, no NFT is escrowed.
+/// * `units` - The number of items, one per winner. It must match `token_ids` in a sale.
 /// * `target_price` - The price expected for the auction to be successful.
 /// * `duration` - The duration for which the auction will be active, measured in blockchain blocks or seconds, depending on the implementation.
+/// * `pricing_rule` - The rule deciding the uniform price paid by the winners (lowest winning bid or highest losing bid).
+/// * `reserve_price` - The minimum price accepted by the owner, the NFT is not sold if no bid reaches it. In a reverse auction, the maximum price paid by the owner per unit.
+/// * `tie_break` - The policy deciding the winner among the bids tied at the highest amount (earliest bid or pseudo-random).
+/// * `auction_rule` - The rule deciding which bids win (highest bids or lowest unique bids), lowest unique bid auctions must be first-price.
+/// * `direction` - Forward for a sale, or reverse for a procurement where the lowest bids win and are paid from the escrowed maximum price.
+/// * `reveal_bids` - Whether every bid is revealed in the proof once the auction ends.
+/// * `cipher_scheme` - The scheme the bidders use to encrypt their bids with `pk_encryption`.
+/// * `payout_terms` - The protocol fee and the royalty taken from the proceeds of a sale, in basis points, with their recipients. Both must be 0 in a reverse auction.
 pub async fn create_new_auction(
     signer: EthSigner,
     auction_contract_address: Address,
@@ -38,30 +53,61 @@ pub async fn create_new_auction(
     name: String,
     description: String,
     nft_contract_address: Address,
-    token_id: U256,
+    token_ids: Vec<U256>,
+    units: u32,
     target_price: U256,
     duration: U256,
+    pricing_rule: PricingRule,
+    reserve_price: u128,
+    tie_break: TieBreak,
+    auction_rule: AuctionRule,
+    direction: AuctionDirection,
+    reveal_bids: bool,
+    cipher_scheme: CipherScheme,
+    payout_terms: PayoutTerms,
 ) -> Result<U256> {
-    // Approve NFT
+    // Approve NFTs
     let erc721_contract = nftContract::new(nft_contract_address, signer.clone().into());
-    let erc721_contract_caller = erc721_contract.approve(auction_contract_address, token_id);
-    let approve_tx = erc721_contract_caller.send().await?;
-    let approve_receipt = approve_tx.await?.unwrap();
-    println!("==========================================================================");
-    println!("Approve NFT successfully with:",);
-    println!("Token ID: {:?}", token_id);
-    println!("Tx: {:?}", approve_receipt.transaction_hash);
+    for token_id in &token_ids {
+        let erc721_contract_caller = erc721_contract.approve(auction_contract_address, *token_id);
+        let approve_tx = erc721_contract_caller.send().await?;
+        let approve_receipt = approve_tx.await?.unwrap();
+        println!("==========================================================================");
+        println!("Approve NFT successfully with:",);
+        println!("Token ID: {:?}", token_id);
+        println!("Tx: {:?}", approve_receipt.transaction_hash);
+    }
+    if direction == AuctionDirection::Reverse {
+        // Approve the escrow of the maximum payment
+        let escrow = U256::from(reserve_price) * U256::from(units);
+        let erc20_contract = erc20Contract::new(token_addr, signer.clone().into());
+        let erc20_contract_caller = erc20_contract.approve(auction_contract_address, escrow);
+        let approve_tx = erc20_contract_caller.send().await?;
+        let approve_receipt = approve_tx.await?.unwrap();
+        println!("==========================================================================");
+        println!("Approve {} token successfully with:", escrow);
+        println!("Tx: {:?}", approve_receipt.transaction_hash);
+    }
     // Create Auction
     let zk_auction_contract = zkAuctionContract::new(auction_contract_address, signer.into());
     let contract_caller = zk_auction_contract.create_auction(
         Bytes::from(pbk_encryption.serialize()),
         token_addr,
         nft_contract_address,
-        token_id,
+        token_ids,
+        units,
         name,
         description,
         target_price,
         duration,
+        pricing_rule as u8,
+        reserve_price,
+        tie_break as u8,
+        auction_rule as u8,
+        direction as u8,
+        reveal_bids,
+        cipher_scheme as u8,
+        payout_terms,
     );
     let tx = contract_caller.send().await?;
     let receipt = tx.await?.unwrap();
@@ -94,47 +140,124 @@ pub async fn create_new_auction(
 /// Returns a tuple containing:
 /// - `owner` - The address of the auction owner.
 /// - `encryption_key` - The public key used for encrypted bids.
-/// - `asset` - Struct holding information about the auctioned item (name, description, NFT contract address, token ID).
-/// - `winner` - Struct containing details of the current highest bidder, including address and encrypted price.
+/// - `asset` - Struct holding information about the auctioned items (name, description, NFT contract address, token IDs).
+/// - `winners` - The addresses of the winners, empty until the auction is finalized.
+/// - `clearing_price` - The price paid by every winner.
 /// - `deposit_price` - The minimum deposit price for the auction in USDT.
 /// - `end_time` - The auction end timestamp.
 /// - `ended` - A boolean indicating whether the auction has ended.
+/// - `pricing_rule` - The rule deciding the price paid by the winner.
+/// - `reserve_price` - The minimum price accepted by the owner.
+/// - `tie_break` - The policy deciding the winner among tied bids.
+/// - `auction_rule` - The rule deciding which bids win.
+/// - `direction` - Whether the highest (sale) or the lowest (procurement) bids win.
+/// - `reveal_bids` - Whether every bid is revealed once the auction ends.
+/// - `cipher_scheme` - The scheme used to encrypt the bids.
+/// - `payout_terms` - The protocol fee and the royalty taken from the proceeds, with their recipients.
+/// - `units` - The number of items, one per winner.
 pub async fn get_auction(
     signer: EthSigner,
     auction_contract_address: Address,
     auction_id: U256,
-) -> Result<(Address, Bytes, Address, Asset, Winner, U256, U256, bool)> {
+) -> Result<(
+    Address,
+    Bytes,
+    Address,
+    Asset,
+    Vec<Address>,
+    u128,
+    U256,
+    U256,
+    bool,
+    PricingRule,
+    u128,
+    TieBreak,
+    AuctionRule,
+    AuctionDirection,
+    bool,
+    CipherScheme,
+    PayoutTerms,
+    u32,
+)> {
     let contract = zkAuctionContract::new(auction_contract_address, signer.into());
-    let (owner, encryption_key, token_addr, asset, winner, deposit_price, end_time, ended) =
-        contract.auctions(auction_id).call().await?;
+    let (
+        owner,
+        encryption_key,
+        token_addr,
+        asset,
+        clearing_price,
+        deposit_price,
+        end_time,
+        ended,
+        pricing_rule,
+        reserve_price,
+        tie_break,
+        auction_rule,
+        direction,
+        reveal_bids,
+        cipher_scheme,
+        payout_terms,
+        units,
+    ) = contract.auctions(auction_id).call().await?;
+    let winners = contract.get_winners(auction_id).call().await?;
+    let pricing_rule = PricingRule::try_from(pricing_rule).map_err(|e| anyhow!(e))?;
+    let tie_break = TieBreak::try_from(tie_break).map_err(|e| anyhow!(e))?;
+    let auction_rule = AuctionRule::try_from(auction_rule).map_err(|e| anyhow!(e))?;
+    let direction = AuctionDirection::try_from(direction).map_err(|e| anyhow!(e))?;
+    let cipher_scheme = CipherScheme::try_from(cipher_scheme).map_err(|e| anyhow!(e))?;
     println!("==========================================================================");
     println!("Auction Details:");
     println!("Name: {}", asset.name);
     println!("Seller: {:?}", owner);
-    println!("Seller's public encryption key: {:?}", encryption_key);
+    println!("Seller's public encryption key: {}", encryption_key);
     println!("Token address: {}", &token_addr);
     println!("Description: {}", asset.description);
     println!("Item:");
     println!("  Address of NFT Contract: {:?}", asset.nft_contract);
-    println!("  Token ID: {:?}", asset.token_id);
-    println!("Winner:");
-    println!("  Address: {:?}", winner.winner);
-    println!("  Encrypted Price: {:?}", winner.price);
+    println!("  Token IDs: {:?}", asset.token_ids);
+    println!("Units: {}", units);
+    println!("Winners: {:?}", winners);
+    println!("Clearing price: {:?}", clearing_price);
     println!("Deposit price: {:?} USDT", deposit_price);
     println!(
         "End Time: {:?}",
         Utc.timestamp_opt(end_time.as_u128() as i64, 0).unwrap()
     );
     println!("Ended: {}", ended);
+    println!("Pricing rule: {:?}", pricing_rule);
+    println!("Reserve price: {:?}", reserve_price);
+    println!("Tie-break: {:?}", tie_break);
+    println!("Auction rule: {:?}", auction_rule);
+    println!("Direction: {:?}", direction);
+    println!("Reveal bids: {}", reveal_bids);
+    println!("Cipher scheme: {:?}", cipher_scheme);
+    println!(
+        "Protocol fee: {} bps to {:?}",
+        payout_terms.fee_bps, payout_terms.fee_recipient
+    );
+    println!(
+        "Royalty: {} bps to {:?}",
+        payout_terms.royalty_bps, payout_terms.royalty_recipient
+    );
     Ok((
         owner,
         encryption_key,
         token_addr,
         asset,
-        winner,
+        winners,
+        clearing_price,
         deposit_price,
         end_time,
         ended,
+        pricing_rule,
+        reserve_price,
+        tie_break,
+        auction_rule,
+        direction,
+        reveal_bids,
+        cipher_scheme,
+        payout_terms,
+        units,
     ))
 }
 
@@ -177,11 +300,35 @@ pub async fn create_bid(
     auction_id: U256,
     bid_price: u128,
 ) -> Result<()> {
-    // let auction = get_auction(signer.clone(), auction_contract_address, auction_id).await?;
-    let (_, encryption_key, token_address, _, _, deposit_price, _, _) =
-        get_auction(signer.clone(), auction_contract_address, auction_id).await?;
-    if U256::from(bid_price) > deposit_price {
-        return Err(anyhow!("You need bid with price < deposit price"));
+    let (
+        _,
+        encryption_key,
+        token_address,
+        _,
+        _,
+        _,
+        deposit_price,
+        _,
+        _,
+        _,
+        reserve_price,
+        _,
+        _,
+        direction,
+        _,
+        cipher_scheme,
+        _,
+        _,
+    ) = get_auction(signer.clone(), auction_contract_address, auction_id).await?;
+    // Defense in depth, the zkVM program rejects the bids above the bound anyway
+    match direction {
+        AuctionDirection::Forward if U256::from(bid_price) > deposit_price => {
+            return Err(anyhow!("You need bid with price < deposit price"));
+        }
+        AuctionDirection::Reverse if bid_price > reserve_price => {
+            return Err(anyhow!("You need bid with price <= maximum price"));
+        }
+        _ => {}
     }
     // Approve token
     let erc20_contract = erc20Contract::new(token_address, signer.clone().into());
@@ -197,7 +344,13 @@ pub async fn create_bid(
     let encryption_key = PublicKey::parse((*encryption_key.to_vec()).try_into()?)
         .expect("Wrong on-chain encryption key");
     // Encrypted price
-    let encrypted_price = encrypt_bidder_amount(&bid_price, &encryption_key);
+    let encrypted_price = encrypt_bidder_amount(
+        &bid_price,
+        &signer.address(),
+        &auction_id,
+        &encryption_key,
+        cipher_scheme,
+    );
 
     // Create bid
     let contract = zkAuctionContract::new(auction_contract_address, signer.into());
@@ -247,6 +400,143 @@ pub async fn get_list_bids(
     Ok(list_bids)
 }
 
+/// Gets the data of an auction, as hashed by the contract to check the proof of the auction.
+///
+/// # Arguments
+///
+/// * `signer` - A `SignerMiddleware` configured for interacting with the blockchain and signing transactions.
+/// * `auction_contract_address` - The contract address of the auction platform.
+/// * `auction_id` - ID of the auction, the bid phase must be over.
+/// * `chain_id` - ID of the chain where the auction contract is deployed.
+///
+/// # Returns
+///
+/// Returns a tuple containing the auction data, the public encryption key of the auction and whether the auction has ended.
+async fn get_auction_data(
+    signer: EthSigner,
+    auction_contract_address: Address,
+    auction_id: U256,
+    chain_id: u64,
+) -> Result<(AuctionData, PublicKey, bool)> {
+    let (
+        _,
+        encryption_key,
+        _,
+        _,
+        _,
+        _,
+        deposit_price,
+        _,
+        ended,
+        pricing_rule,
+        reserve_price,
+        tie_break,
+        auction_rule,
+        direction,
+        reveal_bids,
+        cipher_scheme,
+        payout_terms,
+        units,
+    ) = get_auction(signer.clone(), auction_contract_address, auction_id).await?;
+    let encryption_key = PublicKey::parse((*encryption_key.to_vec()).try_into()?)
+        .expect("Wrong on-chain encryption key");
+    // Get list bids
+    let bidders = get_list_bids(signer, auction_contract_address, auction_id)
+        .await
+        .context(format!(
+            "Failed to get list bids from auction with id: {}",
+            auction_id
+        ))?;
+    let mut auc_id = [0; 32];
+    auction_id.to_big_endian(&mut auc_id);
+    let auction = AuctionData {
+        bidders,
+        id: auc_id.to_vec(),
+        pricing_rule,
+        reserve_price,
+        tie_break,
+        units,
+        version: AUCTION_DATA_VERSION,
+        chain_id,
+        contract_address: auction_contract_address.as_bytes().to_vec(),
+        auction_rule,
+        direction,
+        deposit_price: deposit_price.as_u128(),
+        reveal_bids,
+        cipher_scheme,
+        fee_bps: payout_terms.fee_bps,
+        fee_recipient: payout_terms.fee_recipient.as_bytes().to_vec(),
+        royalty_bps: payout_terms.royalty_bps,
+        royalty_recipient: payout_terms.royalty_recipient.as_bytes().to_vec(),
+    };
+    Ok((auction, encryption_key, ended))
+}
+
+/// Generates the proof that a bid is included in the Merkle root of the bids proven for an auction.
+///
+/// # Arguments
+///
+/// * `signer` - A `SignerMiddleware` configured for interacting with the blockchain and signing transactions.
+/// * `auction_contract_address` - The contract address of the auction platform.
+/// * `auction_id` - ID of the auction, finalized with a proof.
+/// * `bidder` - Address of the bidder whose bid is checked.
+///
+/// # Returns
+///
+/// Returns a tuple containing the proven Merkle root of the bids, emitted by the contract when the auction is finalized, and the inclusion proof of the bid, from the leaf to the root.
+pub async fn get_inclusion_proof(
+    signer: EthSigner,
+    auction_contract_address: Address,
+    auction_id: U256,
+    bidder: Address,
+) -> Result<([u8; 32], Vec<[u8; 32]>)> {
+    let bidders = get_list_bids(signer.clone(), auction_contract_address, auction_id)
+        .await
+        .context(format!(
+            "Failed to get list bids from auction with id: {}",
+            auction_id
+        ))?;
+    // The contract emits the root committed by the proof it verified
+    let contract = zkAuctionContract::new(auction_contract_address, signer.into());
+    let root = contract
+        .event::<BidsProvenFilter>()
+        .from_block(0)
+        .topic1(auction_id)
+        .query()
+        .await?
+        .first()
+        .map(|event| event.bids_root)
+        .ok_or_else(|| anyhow!("Auction {} is not finalized with a proof", auction_id))?;
+    let index = bidders
+        .iter()
+        .position(|element| element.address == bidder.as_bytes())
+        .ok_or_else(|| anyhow!("No bid from {:?} in auction {}", bidder, auction_id))?;
+    let proof = inclusion_proof(&bidders, index).expect("Bid index is in range");
+    if !verify_inclusion(
+        &root,
+        bid_leaf(&bidders[index]),
+        index,
+        bidders.len(),
+        &proof,
+    ) {
+        return Err(anyhow!(
+            "Bid of {:?} is not included in the bids proven for auction {}",
+            bidder,
+            auction_id
+        ));
+    }
+    println!("==========================================================================");
+    println!("Bid included successfully with:");
+    println!("Bidder address: {:?}", bidder);
+    println!("Bid index: {}", index);
+    println!("Merkle root: 0x{}", hex::encode(root));
+    println!("Merkle proof:");
+    for sibling in &proof {
+        println!("  0x{}", hex::encode(sibling));
+    }
+    Ok((root, proof))
+}
+
 /// Reveals the auction winner.
 ///
 /// # Arguments
@@ -258,6 +548,11 @@ pub async fn get_list_bids(
 /// * `rpc_url` - URL of the Ethereum node to connect to.
 /// * `network` - The network on which the auction is deployed (e.g., Ethereum mainnet or testnet).
 /// * `batcher_url` - URL of the batcher service for processing ZKP proofs.
+/// * `chain_id` - ID of the chain where the auction contract is deployed, mixed into the auction hash with the contract address.
+/// * `chunk_size` - Number of bids decrypted by each chunk proof, the chunk proofs are aggregated in the final proof. `None` proves every bid at once.
+/// * `artifacts_dir` - Directory holding the artifact directory of each reveal.
+/// * `resume` - Whether to resume a previous reveal from its last completed stage.
+/// * `key_store` - Key store holding the private key of the auction's encryption key.
 ///
 /// # Returns
 ///
@@ -265,10 +560,8 @@ pub async fn get_list_bids(
 ///
 /// # Workflow
 ///
-/// 1. Retrieves the list of bidders for the specified auction.
-/// 2. Calls an external function, `get_winner_and_submit_proof`, which determines the winner and generates a ZKP.
-/// 3. Submits the proof and winner information to the smart contract's `finalize_auction` function.
-/// 4. Processes transaction logs to verify the result.
+/// Same as `reveal_winners` with a single auction.
+#[allow(clippy::too_many_arguments)]
 pub async fn reveal_winner(
     signer: EthSigner,
     auction_contract_address: Address,
@@ -277,53 +570,299 @@ pub async fn reveal_winner(
     rpc_url: &str,
     network: Network,
     batcher_url: &str,
+    chain_id: u64,
+    chunk_size: Option<usize>,
+    artifacts_dir: &str,
+    resume: bool,
+    key_store: &KeyStore,
 ) -> Result<()> {
-    // Get list bids
-    let bidders = get_list_bids(signer.clone(), auction_contract_address, auction_id)
-        .await
-        .context(format!(
-            "Failed to get list bids from auction with id: {}",
+    reveal_winners(
+        signer,
+        auction_contract_address,
+        vec![auction_id],
+        wallet,
+        rpc_url,
+        network,
+        batcher_url,
+        chain_id,
+        chunk_size,
+        artifacts_dir,
+        resume,
+        key_store,
+    )
+    .await
+}
+
+/// Reveals the winners of several auctions with one shared proof.
+///
+/// # Arguments
+///
+/// * `signer` - A `SignerMiddleware` configured for interacting with the blockchain and signing transactions.
+/// * `auction_contract_address` - The contract address of the auction platform.
+/// * `auction_ids` - IDs of the auctions, all created with the same encryption key.
+/// * `wallet` - Wallet used to sign the winner's proof and other operations.
+/// * `rpc_url` - URL of the Ethereum node to connect to.
+/// * `network` - The network on which the auction is deployed (e.g., Ethereum mainnet or testnet).
+/// * `batcher_url` - URL of the batcher service for processing ZKP proofs.
+/// * `chain_id` - ID of the chain where the auction contract is deployed, mixed into the auction hash with the contract address.
+/// * `chunk_size` - Number of bids decrypted by each chunk proof, the chunk proofs are aggregated in the final proof. `None` proves every bid at once.
+/// * `artifacts_dir` - Directory holding the artifact directory of each reveal, one per contract and set of auctions.
+/// * `resume` - Whether to resume a previous reveal from its last completed stage, e.g. resubmitting an existing proof or only finalizing the auctions. Without it, a reveal with saved progress is refused.
+/// * `key_store` - Key store holding the private key of the encryption key shared by the auctions, found among its keys.
+///
+/// # Returns
+///
+/// Returns `Result<()>` indicating success or failure.
+///
+/// # Workflow
+///
+/// 0. Auctions that already ended are skipped. Auctions that received no bid are reclaimed with `reclaim_auction` instead, no proof is needed for them.
+/// 1. Retrieves the encryption key, whose private key is looked up in `key_store` and unlocked by password when the bids are decrypted, the auction rule, the direction, the pricing rule, the reserve price, the deposit price, the number of units and the list of bidders for each auction.
+/// 2. Calls an external function, `get_winners_and_submit_proof`, which determines the winners and the clearing price of every auction and generates a single ZKP.
+///    Bids above the deposit price of a sale are rejected, so they can not make the auction unfinalizable.
+/// 3. Submits the shared proof with the winners of each auction to the smart contract's `finalize_auction` function, which finds the outcome of the auction in the proof and charges every winner the clearing price.
+///    The proceeds are paid out as split by the proof between the owner, the fee recipient and the royalty recipient.
+///    Units without a winner go back to the owner. If no bid reaches the reserve price, the list of winners is empty and every deposit is unlocked.
+/// 4. Processes transaction logs to verify the result.
+///
+/// The artifact of each stage and the finalized auctions are recorded in the artifact directory, see `ArtifactDir`.
+#[allow(clippy::too_many_arguments)]
+pub async fn reveal_winners(
+    signer: EthSigner,
+    auction_contract_address: Address,
+    auction_ids: Vec<U256>,
+    wallet: Wallet<SigningKey>,
+    rpc_url: &str,
+    network: Network,
+    batcher_url: &str,
+    chain_id: u64,
+    chunk_size: Option<usize>,
+    artifacts_dir: &str,
+    resume: bool,
+    key_store: &KeyStore,
+) -> Result<()> {
+    let artifact_dir =
+        ArtifactDir::for_auctions(artifacts_dir, &auction_contract_address, &auction_ids)?;
+    let manifest = artifact_dir.manifest()?;
+    if !resume && manifest.stage.is_some() {
+        return Err(anyhow!(
+            "A previous reveal saved its progress in {}, resume it or delete the directory",
+            artifact_dir.path().display()
+        ));
+    }
+    // The auctions of the saved proof are finalized, they must be the requested ones
+    if let Some(auction_id) = manifest
+        .auction_ids
+        .iter()
+        .find(|auction_id| !auction_ids.contains(auction_id))
+    {
+        return Err(anyhow!(
+            "Progress saved in {} proves auction {} which is not revealed, delete the directory",
+            artifact_dir.path().display(),
             auction_id
+        ));
+    }
+
+    let mut shared_encryption_key: Option<PublicKey> = None;
+    let mut open_auction_ids = vec![];
+    let mut auctions = vec![];
+    for auction_id in auction_ids {
+        let (auction, encryption_key, ended) = get_auction_data(
+            signer.clone(),
+            auction_contract_address,
+            auction_id,
+            chain_id,
+        )
+        .await?;
+        if ended {
+            println!("Auction {} has already ended", auction_id);
+            continue;
+        }
+        println!("bidders of auction {}: {:?}", auction_id, auction.bidders);
+        if auction.bidders.is_empty() {
+            println!("Auction {} has no bids, reclaiming the NFT...", auction_id);
+            reclaim_auction(signer.clone(), auction_contract_address, auction_id).await?;
+            continue;
+        }
+        // One proof can only decrypt the bids of auctions sharing the same key
+        match shared_encryption_key {
+            Some(shared) if shared != encryption_key => {
+                return Err(anyhow!(
+                    "Auction {} does not use the same encryption key as the other auctions",
+                    auction_id
+                ));
+            }
+            _ => shared_encryption_key = Some(encryption_key),
+        }
+        open_auction_ids.push(auction_id);
+        auctions.push(auction);
+    }
+    let Some(encryption_key) = shared_encryption_key else {
+        return Ok(());
+    };
+    let key_name = key_store.find_key(&encryption_key)?;
+    println!("Encryption key of the auctions: {}", key_name);
+    // The password is only asked when the bids are decrypted, not when resuming after the proof
+    let unlock_key = || {
+        let password = rpassword::prompt_password(format!(
+            "Enter password of the encryption key {}: ",
+            key_name
         ))?;
-    println!("bidders: {:?}", bidders);
+        key_store.private_key(&key_name, &password)
+    };
+
     //Send to SP1
-    let mut auc_id = [0; 32];
-    auction_id.to_big_endian(&mut auc_id);
-    let (winner_addr, winner_amount, verified_proof) = get_winner_and_submit_proof(
+    let (batch, verified_proof) = get_winners_and_submit_proof(
         wallet,
-        &AuctionData {
-            bidders,
-            id: auc_id.to_vec(),
-        },
+        &auctions,
+        unlock_key,
         rpc_url,
         network,
         batcher_url,
+        chunk_size,
+        &artifact_dir,
     )
     .await?;
 
-    // Submit proof to SMC
+    // Submit the shared proof to SMC, each auction checks its own outcome in it
     let contract = zkAuctionContract::new(auction_contract_address, signer.into());
-    let contract_caller = contract.finalize_auction(
-        auction_id,
-        Winner {
-            winner: winner_addr,
-            price: winner_amount,
-        },
-        Bytes::from(verified_proof),
-    );
+    let manifest = artifact_dir.manifest()?;
+    for (auction_id, public_values) in manifest.auction_ids.into_iter().zip(batch) {
+        if manifest.finalized.contains(&auction_id) || !open_auction_ids.contains(&auction_id) {
+            println!("Auction {} is already finalized", auction_id);
+            continue;
+        }
+        let contract_caller = contract.finalize_auction(
+            auction_id,
+            public_values
+                .winners
+                .iter()
+                .map(|winner| Address::from(winner.into_array()))
+                .collect(),
+            public_values.clearing_price,
+            Payout {
+                owner_amount: U256::from_big_endian(
+                    &public_values.payout.owner_amount.to_be_bytes::<32>(),
+                ),
+                fee_amount: U256::from_big_endian(
+                    &public_values.payout.fee_amount.to_be_bytes::<32>(),
+                ),
+                royalty_amount: U256::from_big_endian(
+                    &public_values.payout.royalty_amount.to_be_bytes::<32>(),
+                ),
+            },
+            Bytes::from(verified_proof.clone()),
+        );
+        let tx = contract_caller.send().await?;
+        let receipt = tx.await?.unwrap();
+        artifact_dir.mark_finalized(auction_id)?;
+        let events = receipt.logs;
+        println!("==========================================================================");
+        if !public_values.has_winner() {
+            println!(
+                "No bid of auction {} reaches the reserve price, the NFTs are returned to the owner",
+                auction_id
+            );
+        } else {
+            println!(
+                "Reveal winners of auction {} successfully with:",
+                auction_id
+            );
+            println!("Clearing price: {:?}", public_values.clearing_price);
+            print_payout(&public_values);
+        }
+        // One event is emitted per winner, in the order of the winning bids
+        let mut winner_amounts = public_values.winner_amounts.iter();
+        for log in events {
+            if log.topics[0] == H256::from(keccak256(b"AuctionEnded(uint256,address,uint128)")) {
+                if let Some(winner_amount) = winner_amounts.next() {
+                    println!("Winner address: {:?}", Address::from(log.topics[2]));
+                    println!("Winning bid: {:?}", winner_amount);
+                }
+                println!("Auction ID: {:?}", U256::decode(log.topics[1])?);
+                println!("Block: {:?}", log.block_number.unwrap());
+                println!("Tx: {:?}", log.transaction_hash.unwrap());
+            }
+        }
+        print_revealed_bids(&public_values);
+    }
+    Ok(())
+}
+
+/// Prints the results of the auctions from the public values saved when their proof was created.
+///
+/// # Arguments
+///
+/// * `pub_input_path` - Path of the public values written by `reveal_winner` or `reveal_winners` in the artifact directory of the reveal.
+///
+/// # Returns
+///
+/// Returns the decoded `AuctionPublicValues` of each auction in the proof, including every bid if the auction reveals the bids.
+pub fn get_results(pub_input_path: &str) -> Result<Vec<AuctionPublicValues>> {
+    let pub_input = fs::read(pub_input_path).context(format!(
+        "Failed to read public values from: {}",
+        pub_input_path
+    ))?;
+    let batch = AuctionPublicValues::decode_batch(&pub_input)?;
+    for public_values in &batch {
+        println!("==========================================================================");
+        println!("Auction Results:");
+        println!("Auction hash: {:?}", public_values.auction_hash);
+        println!("Bids root: {:?}", public_values.bids_root);
+        println!("Winners: {:?}", public_values.winners);
+        println!("Winning bids: {:?}", public_values.winner_amounts);
+        println!("Clearing price: {:?}", public_values.clearing_price);
+        println!("Rejected bids: {:?}", public_values.rejected_bids);
+        print_payout(public_values);
+        print_revealed_bids(public_values);
+    }
+    Ok(batch)
+}
+
+fn print_payout(public_values: &AuctionPublicValues) {
+    println!("Paid to the owner: {}", public_values.payout.owner_amount);
+    println!("Protocol fee: {}", public_values.payout.fee_amount);
+    println!("Royalty: {}", public_values.payout.royalty_amount);
+}
+
+fn print_revealed_bids(public_values: &AuctionPublicValues) {
+    if public_values.revealed_bids.is_empty() {
+        return;
+    }
+    println!("Revealed bids:");
+    for bid in &public_values.revealed_bids {
+        if bid.valid {
+            println!("  Bidder: {:?}, amount: {:?}", bid.bidder, bid.amount);
+        } else {
+            println!("  Bidder: {:?}, invalid bid", bid.bidder);
+        }
+    }
+}
+
+/// Ends an auction that received no bid and returns the NFT to the owner.
+///
+/// # Arguments
+///
+/// * `signer` - A `SignerMiddleware` configured for interacting with the blockchain and signing transactions.
+/// * `auction_contract_address` - The contract address of the auction platform.
+/// * `auction_id` - ID of the auction.
+///
+/// # Returns
+///
+/// Returns `Result<()>` indicating success or failure.
+pub async fn reclaim_auction(
+    signer: EthSigner,
+    auction_contract_address: Address,
+    auction_id: U256,
+) -> Result<()> {
+    let contract = zkAuctionContract::new(auction_contract_address, signer.into());
+    let contract_caller = contract.reclaim_auction(auction_id);
     let tx = contract_caller.send().await?;
     let receipt = tx.await?.unwrap();
-    let events = receipt.logs;
     println!("==========================================================================");
-    for log in events {
-        if log.topics[0] == H256::from(keccak256(b"AuctionEnded(uint256,address,uint128)")) {
-            println!("Reveal winner successfully with:");
-            println!("Winner address: {:?}", Address::from(log.topics[2]));
-            println!("Auction ID: {:?}", U256::decode(log.topics[1])?);
-            println!("Block: {:?}", log.block_number.unwrap());
-            println!("Tx: {:?}", log.transaction_hash.unwrap());
-        }
-    }
+    println!("Reclaim NFT successfully with:");
+    println!("Auction ID: {:?}", auction_id);
+    println!("Tx: {:?}", receipt.transaction_hash);
     Ok(())
 }
 
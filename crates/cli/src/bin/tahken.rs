@@ -1,7 +1,9 @@
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
 
 use aligned_sdk::core::types::Network;
+use aligned_sp1_prover::{AuctionDirection, AuctionRule, CipherScheme, PricingRule, TieBreak};
 use anyhow::Result;
 use clap::CommandFactory;
 use clap::{Parser, Subcommand};
@@ -9,11 +11,13 @@ use ethers::core::k256::ecdsa::SigningKey;
 use ethers::prelude::*;
 use ethers::providers::Provider;
 use ethers::signers::{LocalWallet, Signer};
-use prover_sdk::get_encryption_key;
+use prover_sdk::{KeyStore, DEFAULT_KEY_NAME};
 use zk_auction::auction::{
-    create_bid, create_new_auction, get_auction, get_total_auction, reveal_winner, withdraw,
+    create_bid, create_new_auction, get_auction, get_inclusion_proof, get_results,
+    get_total_auction, reveal_winner, reveal_winners, withdraw, PayoutTerms,
 };
 use zk_auction::config::Config;
+use zk_auction::keygen::{export_key, generate_key, import_key, list_keys, print_fingerprint};
 
 #[derive(Parser, Debug)]
 #[command(name = "tahken")]
@@ -25,6 +29,12 @@ struct Cli {
     version: bool,
     #[clap(short, long, default_value = "config.toml")]
     config_path: String,
+    /// Directory of the encryption keys, overrides `keys.dir` of the config
+    #[clap(long)]
+    keys_dir: Option<PathBuf>,
+    /// Name of the encryption key used to create auctions, overrides `keys.name` of the config
+    #[clap(long)]
+    key: Option<String>,
 }
 
 #[derive(Subcommand, Clone, Debug, PartialEq)]
@@ -37,10 +47,15 @@ enum Commands {
         name: String,
         #[arg(short, long, default_value = "")]
         description: String,
+        /// Contract of the NFTs sold, not needed in a reverse auction
         #[arg(short, long)]
-        nft_contract_address: Address,
-        #[arg(short, long)]
-        token_id: u128,
+        nft_contract_address: Option<Address>,
+        /// IDs of the identical NFTs sold, one per unit, separated by commas
+        #[arg(short, long, value_delimiter = ',')]
+        token_ids: Vec<u128>,
+        /// Number of items procured in a reverse auction, a sale has one unit per token ID
+        #[arg(long)]
+        units: Option<u32>,
         #[arg(short, long)]
         target_price: u128,
         #[arg(short, long, default_value = "3600")]
@@ -53,6 +68,34 @@ enum Commands {
             default_value = "0xd6a367e96abd5872f0e39b9f5df0ed1cd125c41e"
         )]
         token_address: Address,
+        /// Price paid by the winner: `first-price` or `second-price` (Vickrey)
+        #[arg(short, long, default_value = "first-price")]
+        pricing_rule: PricingRule,
+        /// Minimum price accepted, the NFT is not sold if no bid reaches it. In a reverse auction,
+        /// the maximum price paid per unit, escrowed at creation
+        #[arg(short, long, default_value = "0")]
+        reserve_price: u128,
+        /// Winner among the bids tied at the highest amount: `earliest` or `random`
+        #[arg(long, default_value = "earliest")]
+        tie_break: TieBreak,
+        /// Winning bids: `highest-bid` or `lowest-unique-bid`, which must be first-price
+        #[arg(long, default_value = "highest-bid")]
+        auction_rule: AuctionRule,
+        /// `forward` (sale) or `reverse` (procurement, the lowest bids win)
+        #[arg(long, default_value = "forward")]
+        direction: AuctionDirection,
+        /// Reveal every bid in the proof once the auction ends
+        #[arg(long)]
+        reveal_bids: bool,
+        /// Scheme the bidders use to encrypt their bids: `ecies`
+        #[arg(long, default_value = "ecies")]
+        cipher_scheme: CipherScheme,
+        /// Royalty taken from the proceeds of a sale, in basis points
+        #[arg(long, default_value = "0")]
+        royalty_bps: u16,
+        /// Address receiving the royalty, required with a royalty
+        #[arg(long)]
+        royalty_recipient: Option<Address>,
     },
     /// Get detail auctions
     GetAuction {
@@ -81,6 +124,54 @@ enum Commands {
         auction_id: u128,
         #[clap(short, long)]
         keystore_path: String,
+        /// Prove the bids by chunks of this size, then aggregate the chunk proofs
+        #[arg(long)]
+        chunk_size: Option<usize>,
+        /// Directory holding the artifacts of each reveal
+        #[arg(long, default_value = "artifacts")]
+        artifacts_dir: String,
+        /// Resume a previous reveal from its last completed stage
+        #[arg(long)]
+        resume: bool,
+    },
+    /// Reveal the winners of several auctions with one shared proof
+    RevealWinners {
+        /// IDs of the auctions, separated by commas
+        #[arg(short, long, value_delimiter = ',', required = true)]
+        auction_ids: Vec<u128>,
+        #[clap(short, long)]
+        keystore_path: String,
+        /// Prove the bids by chunks of this size, then aggregate the chunk proofs
+        #[arg(long)]
+        chunk_size: Option<usize>,
+        /// Directory holding the artifacts of each reveal
+        #[arg(long, default_value = "artifacts")]
+        artifacts_dir: String,
+        /// Resume a previous reveal from its last completed stage
+        #[arg(long)]
+        resume: bool,
+    },
+    /// Print the results saved when revealing the winner, including every bid if revealed
+    GetResults {
+        /// Public values in the artifact directory of the reveal, e.g.
+        /// `artifacts/<contract address>/auction-<id>/pub_input`
+        #[arg(short, long)]
+        pub_input_path: String,
+    },
+    /// Prove that a bid is included in the bids of an auction
+    ProveInclusion {
+        #[arg(short, long)]
+        auction_id: u128,
+        #[clap(short, long)]
+        keystore_path: String,
+        /// Address of the bidder, the address of the keystore by default
+        #[arg(short, long)]
+        bidder: Option<Address>,
+    },
+    /// Manage the encryption keys of the owner
+    Keygen {
+        #[command(subcommand)]
+        command: KeygenCommands,
     },
     /// Withdraw deposit token
     Withdraw {
@@ -91,6 +182,42 @@ enum Commands {
     },
 }
 
+#[derive(Subcommand, Clone, Debug, PartialEq)]
+enum KeygenCommands {
+    /// Generate an encryption key, encrypted with a password
+    Generate {
+        /// Name of the key, `--key` by default
+        #[arg(short, long)]
+        name: Option<String>,
+    },
+    /// Import a private key, hex-encoded or exported, and encrypt it with a password
+    Import {
+        /// Name of the key, `--key` by default
+        #[arg(short, long)]
+        name: Option<String>,
+        /// File of the private key
+        #[arg(short, long)]
+        file: PathBuf,
+    },
+    /// Export an encrypted private key
+    Export {
+        /// Name of the key, `--key` by default
+        #[arg(short, long)]
+        name: Option<String>,
+        /// File written with the encrypted key
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// List the keys with their fingerprint
+    List,
+    /// Print the public key, as stored on-chain, and its fingerprint
+    Fingerprint {
+        /// Name of the key, `--key` by default
+        #[arg(short, long)]
+        name: Option<String>,
+    },
+}
+
 #[allow(clippy::needless_return)]
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -107,6 +234,14 @@ async fn main() -> Result<()> {
     let rpc_url = config.chain.rpc_url.as_str();
     let network = Network::from_str(&config.chain.network).unwrap();
     let aligned_batcher_url = config.chain.aligned_batcher_url.as_str();
+    let key_store = KeyStore::new(match args.keys_dir.or(config.keys.dir.clone()) {
+        Some(keys_dir) => keys_dir,
+        None => KeyStore::default_dir()?,
+    });
+    let key_name = args
+        .key
+        .or(config.keys.name.clone())
+        .unwrap_or(DEFAULT_KEY_NAME.to_string());
 
     match args.command {
         Some(command) => match command {
@@ -118,15 +253,51 @@ async fn main() -> Result<()> {
                 name,
                 description,
                 nft_contract_address,
-                token_id,
+                token_ids,
+                units,
                 target_price,
                 time,
                 keystore_path,
                 token_address,
+                pricing_rule,
+                reserve_price,
+                tie_break,
+                auction_rule,
+                direction,
+                reveal_bids,
+                cipher_scheme,
+                royalty_bps,
+                royalty_recipient,
             } => {
+                if royalty_bps > 0 && royalty_recipient.is_none() {
+                    panic!("Missing royalty recipient");
+                }
+                // The NFTs are only escrowed in a sale, a reverse auction procures its units
+                let units = match direction {
+                    AuctionDirection::Forward => {
+                        if token_ids.is_empty() || nft_contract_address.is_none() {
+                            panic!("Missing NFTs to sell");
+                        }
+                        if units.is_some_and(|units| units as usize != token_ids.len()) {
+                            panic!("Units must match the token IDs");
+                        }
+                        token_ids.len() as u32
+                    }
+                    AuctionDirection::Reverse => {
+                        if !token_ids.is_empty() {
+                            panic!("Reverse auctions do not escrow NFTs");
+                        }
+                        units.unwrap_or_else(|| panic!("Missing units to procure"))
+                    }
+                };
+                // The marketplace fee is only taken from the proceeds of a sale
+                let marketplace = match direction {
+                    AuctionDirection::Forward => config.marketplace.clone(),
+                    AuctionDirection::Reverse => Default::default(),
+                };
                 let (signer, _wallet_address, _wallet) =
                     set_up_wallet(config.clone(), keystore_path).await;
-                let encryption_key = get_encryption_key()?;
+                let encryption_key = key_store.public_key(&key_name)?;
                 create_new_auction(
                     signer,
                     config.contract_address,
@@ -134,10 +305,24 @@ async fn main() -> Result<()> {
                     token_address,
                     name,
                     description,
-                    nft_contract_address,
-                    U256::from(token_id),
+                    nft_contract_address.unwrap_or_default(),
+                    token_ids.into_iter().map(U256::from).collect(),
+                    units,
                     U256::from(target_price),
                     U256::from(time),
+                    pricing_rule,
+                    reserve_price,
+                    tie_break,
+                    auction_rule,
+                    direction,
+                    reveal_bids,
+                    cipher_scheme,
+                    PayoutTerms {
+                        fee_bps: marketplace.fee_bps,
+                        fee_recipient: marketplace.fee_recipient,
+                        royalty_bps,
+                        royalty_recipient: royalty_recipient.unwrap_or_default(),
+                    },
                 )
                 .await
                 .unwrap_or_else(|e| {
@@ -192,6 +377,9 @@ async fn main() -> Result<()> {
             Commands::RevealWinner {
                 auction_id,
                 keystore_path,
+                chunk_size,
+                artifacts_dir,
+                resume,
             } => {
                 let (signer, _, wallet) = set_up_wallet(config.clone(), keystore_path).await;
                 reveal_winner(
@@ -202,6 +390,11 @@ async fn main() -> Result<()> {
                     rpc_url,
                     network,
                     aligned_batcher_url,
+                    config.chain.chain_id,
+                    chunk_size,
+                    &artifacts_dir,
+                    resume,
+                    &key_store,
                 )
                 .await
                 .unwrap_or_else(|e| {
@@ -210,6 +403,93 @@ async fn main() -> Result<()> {
                 });
                 Ok(())
             }
+            Commands::RevealWinners {
+                auction_ids,
+                keystore_path,
+                chunk_size,
+                artifacts_dir,
+                resume,
+            } => {
+                let (signer, _, wallet) = set_up_wallet(config.clone(), keystore_path).await;
+                reveal_winners(
+                    signer,
+                    config.contract_address,
+                    auction_ids.iter().copied().map(U256::from).collect(),
+                    wallet,
+                    rpc_url,
+                    network,
+                    aligned_batcher_url,
+                    config.chain.chain_id,
+                    chunk_size,
+                    &artifacts_dir,
+                    resume,
+                    &key_store,
+                )
+                .await
+                .unwrap_or_else(|e| {
+                    println!("{}", e);
+                    panic!(
+                        "Failed to reveal winners of auctions with ids: {:?}",
+                        auction_ids
+                    );
+                });
+                Ok(())
+            }
+            Commands::GetResults { pub_input_path } => {
+                get_results(&pub_input_path).unwrap_or_else(|e| {
+                    println!("{}", e);
+                    panic!("Failed to get results from: {}", pub_input_path);
+                });
+                Ok(())
+            }
+            Commands::ProveInclusion {
+                auction_id,
+                keystore_path,
+                bidder,
+            } => {
+                let (signer, wallet_address, _) =
+                    set_up_wallet(config.clone(), keystore_path).await;
+                get_inclusion_proof(
+                    signer,
+                    config.contract_address,
+                    U256::from(auction_id),
+                    bidder.unwrap_or(wallet_address),
+                )
+                .await
+                .unwrap_or_else(|e| {
+                    println!("{}", e);
+                    panic!(
+                        "Failed to prove inclusion in auction with id: {}",
+                        auction_id
+                    );
+                });
+                Ok(())
+            }
+            Commands::Keygen { command } => {
+                match command {
+                    KeygenCommands::Generate { name } => {
+                        generate_key(&key_store, &name.unwrap_or(key_name))
+                    }
+                    KeygenCommands::Import { name, file } => {
+                        import_key(&key_store, &name.unwrap_or(key_name), &file)
+                    }
+                    KeygenCommands::Export { name, output } => {
+                        export_key(&key_store, &name.unwrap_or(key_name), &output)
+                    }
+                    KeygenCommands::List => list_keys(&key_store),
+                    KeygenCommands::Fingerprint { name } => {
+                        print_fingerprint(&key_store, &name.unwrap_or(key_name))
+                    }
+                }
+                .unwrap_or_else(|e| {
+                    println!("{}", e);
+                    panic!(
+                        "Failed to manage encryption keys in {}",
+                        key_store.dir().display()
+                    );
+                });
+                Ok(())
+            }
             Commands::Withdraw {
                 auction_id,
                 keystore_path,
@@ -245,6 +525,12 @@ async fn set_up_wallet(
         .get_chainid()
         .await
         .expect("Failed to get chain_id");
+    if chain_id.as_u64() != config.chain.chain_id {
+        panic!(
+            "Chain id of the rpc ({}) does not match the config ({})",
+            chain_id, config.chain.chain_id
+        );
+    }
 
     let keystore_password = rpassword::prompt_password("Enter keystore password: ")
         .expect("Failed to read keystore password");
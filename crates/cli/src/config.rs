@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use config::{Config as ConfigLoader, File, FileFormat};
 use ethers::types::H160;
 use serde::Deserialize;
@@ -7,12 +9,35 @@ pub struct ChainConfig {
     pub rpc_url: String,
     pub network: String,
     pub aligned_batcher_url: String,
+    pub chain_id: u64,
+}
+
+/// Protocol fee taken by the marketplace from the proceeds of every sale
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct MarketplaceConfig {
+    /// Fee in basis points
+    pub fee_bps: u16,
+    /// Address receiving the fee, required with a fee
+    pub fee_recipient: H160,
+}
+
+/// Key store of the owner's encryption keys, see `KeyStore`
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct KeysConfig {
+    /// Directory of the keys, the per-user data directory if unset
+    pub dir: Option<PathBuf>,
+    /// Name of the key used to create auctions, `default` if unset
+    pub name: Option<String>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct Config {
     pub chain: ChainConfig,
-    pub contract_address: H160
+    pub contract_address: H160,
+    #[serde(default)]
+    pub marketplace: MarketplaceConfig,
+    #[serde(default)]
+    pub keys: KeysConfig,
 }
 
 impl Config {
@@ -0,0 +1,115 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use ecies::PublicKey;
+use prover_sdk::keys::{encode_public_key, fingerprint, KeyStore};
+
+/// Generates a new encryption key pair, encrypted with a password read from the terminal.
+///
+/// # Arguments
+///
+/// * `key_store` - Key store saving the key.
+/// * `name` - Name of the new key.
+///
+/// # Returns
+///
+/// Returns `Result<()>` indicating success or failure.
+pub fn generate_key(key_store: &KeyStore, name: &str) -> Result<()> {
+    let password = prompt_new_password(name)?;
+    let pbk = key_store.generate(name, &password)?;
+    println!("==========================================================================");
+    println!("Generate encryption key successfully with:");
+    print_key(name, &pbk);
+    Ok(())
+}
+
+/// Imports a private encryption key, hex-encoded or exported by `export_key`, and encrypts it with
+/// a password read from the terminal.
+///
+/// # Arguments
+///
+/// * `key_store` - Key store saving the key.
+/// * `name` - Name of the imported key.
+/// * `path` - File of the private key. An exported key is decrypted with the same password.
+///
+/// # Returns
+///
+/// Returns `Result<()>` indicating success or failure.
+pub fn import_key(key_store: &KeyStore, name: &str, path: &Path) -> Result<()> {
+    let password = prompt_new_password(name)?;
+    let pbk = key_store.import(name, path, &password)?;
+    println!("==========================================================================");
+    println!("Import encryption key successfully with:");
+    print_key(name, &pbk);
+    Ok(())
+}
+
+/// Exports an encrypted private encryption key, to import it on another machine.
+///
+/// # Arguments
+///
+/// * `key_store` - Key store holding the key.
+/// * `name` - Name of the key.
+/// * `path` - File written with the encrypted key.
+///
+/// # Returns
+///
+/// Returns `Result<()>` indicating success or failure.
+pub fn export_key(key_store: &KeyStore, name: &str, path: &Path) -> Result<()> {
+    key_store.export(name, path)?;
+    println!("==========================================================================");
+    println!("Export encryption key successfully with:");
+    print_key(name, &key_store.public_key(name)?);
+    println!("File: {}", path.display());
+    Ok(())
+}
+
+/// Lists the keys of the key store with their fingerprint.
+///
+/// # Arguments
+///
+/// * `key_store` - Key store holding the keys.
+///
+/// # Returns
+///
+/// Returns `Result<()>` indicating success or failure.
+pub fn list_keys(key_store: &KeyStore) -> Result<()> {
+    let names = key_store.names()?;
+    println!("Keys in {}: {}", key_store.dir().display(), names.len());
+    for name in names {
+        println!("{}: {}", name, fingerprint(&key_store.public_key(&name)?));
+    }
+    Ok(())
+}
+
+/// Prints the public key of an encryption key, as stored on-chain by `create_new_auction`, and its
+/// fingerprint, the hash of the key bound to the proof of each auction using it.
+///
+/// # Arguments
+///
+/// * `key_store` - Key store holding the key.
+/// * `name` - Name of the key.
+///
+/// # Returns
+///
+/// Returns `Result<()>` indicating success or failure.
+pub fn print_fingerprint(key_store: &KeyStore, name: &str) -> Result<()> {
+    print_key(name, &key_store.public_key(name)?);
+    Ok(())
+}
+
+fn print_key(name: &str, pbk: &PublicKey) {
+    println!("Name: {}", name);
+    println!("Public key: {}", encode_public_key(pbk));
+    println!("Fingerprint: {}", fingerprint(pbk));
+}
+
+fn prompt_new_password(name: &str) -> Result<String> {
+    let password =
+        rpassword::prompt_password(format!("Enter password of the encryption key {}: ", name))?;
+    let confirmation = rpassword::prompt_password("Confirm password: ")?;
+    if password != confirmation {
+        return Err(anyhow!("Passwords do not match"));
+    }
+    Ok(password)
+}
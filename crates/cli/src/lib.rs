@@ -1,4 +1,5 @@
 pub mod auction;
 pub mod config;
+pub mod keygen;
 pub mod tests;
 pub mod types;
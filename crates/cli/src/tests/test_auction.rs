@@ -7,6 +7,7 @@ mod test {
     use std::time::Duration;
 
     use aligned_sdk::core::types::Network;
+    use aligned_sp1_prover::{AuctionDirection, AuctionRule, CipherScheme, PricingRule, TieBreak};
     use ethers::core::k256::ecdsa::SigningKey;
     use ethers::core::rand::rngs::OsRng;
     use ethers::core::rand::RngCore;
@@ -14,11 +15,11 @@ mod test {
     use ethers::providers::Provider;
     use ethers::signers::{LocalWallet, Signer};
     use home::home_dir;
-    use prover_sdk::get_encryption_key;
+    use prover_sdk::{KeyStore, DEFAULT_KEY_NAME};
     use tokio::time::sleep;
 
     use crate::auction::{
-        create_bid, create_new_auction, erc20Contract, nftContract, reveal_winner,
+        create_bid, create_new_auction, erc20Contract, nftContract, reveal_winner, PayoutTerms,
     };
     use crate::config::Config;
     use crate::types::EthSigner;
@@ -58,7 +59,8 @@ mod test {
         // Test create new auction success
         let name = "test".to_string();
         let description = "nothing".to_string();
-        let encryption_key = get_encryption_key().unwrap();
+        let key_store = KeyStore::new(KeyStore::default_dir().unwrap());
+        let encryption_key = key_store.public_key(DEFAULT_KEY_NAME).unwrap();
 
         println!("Creating new auction...");
         // Create new auction
@@ -70,9 +72,23 @@ mod test {
             name,
             description,
             nft_addr(),
-            ntf_id,
+            vec![ntf_id],
+            1,
             U256::from(1000),
             U256::from(auction_time),
+            PricingRule::FirstPrice,
+            0,
+            TieBreak::EarliestBid,
+            AuctionRule::HighestBid,
+            AuctionDirection::Forward,
+            false,
+            CipherScheme::Ecies,
+            PayoutTerms {
+                fee_bps: config.marketplace.fee_bps,
+                fee_recipient: config.marketplace.fee_recipient,
+                royalty_bps: 0,
+                royalty_recipient: Address::zero(),
+            },
         )
         .await
         .unwrap();
@@ -102,6 +118,11 @@ mod test {
             rpc_url,
             network,
             aligned_batcher_url,
+            config.chain.chain_id,
+            None,
+            "artifacts",
+            false,
+            &key_store,
         )
         .await
         .unwrap();
@@ -0,0 +1,396 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use aligned_sp1_prover::{AuctionData, AuctionPublicValues};
+use anyhow::{anyhow, Context, Result};
+use ethers::types::{Address, U256};
+use serde::{Deserialize, Serialize};
+use sp1_sdk::{ProverClient, SP1ProofWithPublicValues};
+
+use crate::get_elf;
+use crate::pipeline::{AuctionProof, BatchedProof, LocallyVerifiedProof, SubmittedProof};
+
+const MANIFEST_FILE: &str = "manifest.json";
+const PROOF_FILE: &str = "proof";
+const PUB_INPUT_FILE: &str = "pub_input";
+const ALIGNED_VERIFICATION_DATA_FILE: &str = "aligned_verification_data.json";
+const VERIFIED_PROOF_FILE: &str = "verified_proof";
+
+/// Stage of the reveal pipeline, in the order they complete
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stage {
+    /// The proof is created, see `prove_auction`
+    Proved,
+    /// The proof matches the outcome computed natively, see `verify_local`
+    VerifiedLocally,
+    /// The proof is submitted to Aligned, its fee is paid, see `submit_to_aligned`
+    Submitted,
+    /// The batch of the proof is verified on-chain, see `wait_for_batch`
+    Batched,
+}
+
+/// Progress of a reveal, saved next to its artifacts
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct Manifest {
+    /// Last completed stage, `None` before the proof is created
+    pub stage: Option<Stage>,
+    /// Ids of the proven auctions, in the order of their public values
+    pub auction_ids: Vec<U256>,
+    /// Ids of the auctions finalized on-chain with the proof
+    pub finalized: Vec<U256>,
+}
+
+/// Directory holding the artifacts of each stage of a reveal and its manifest, so a reveal can
+/// resume from the last completed stage
+pub struct ArtifactDir {
+    path: PathBuf,
+}
+
+impl ArtifactDir {
+    /// Open the artifact directory at `path`, creating it if needed
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: path of the directory
+    ///
+    /// returns: Result<ArtifactDir, Error>
+    pub fn new(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        fs::create_dir_all(&path).context(format!(
+            "Failed to create artifact directory: {}",
+            path.display()
+        ))?;
+        Ok(ArtifactDir { path })
+    }
+
+    /// Open the artifact directory of a reveal, one per contract and set of auctions so reveals
+    /// do not overwrite each other's artifacts
+    ///
+    /// # Arguments
+    ///
+    /// * `base`: directory holding the artifact directories
+    /// * `contract_address`: address of the auction contract
+    /// * `auction_ids`: ids of the revealed auctions
+    ///
+    /// returns: Result<ArtifactDir, Error>
+    pub fn for_auctions(
+        base: impl AsRef<Path>,
+        contract_address: &Address,
+        auction_ids: &[U256],
+    ) -> Result<Self> {
+        let auction_ids: Vec<String> = auction_ids.iter().map(U256::to_string).collect();
+        Self::new(
+            base.as_ref()
+                .join(format!("{:?}", contract_address))
+                .join(format!("auction-{}", auction_ids.join("-"))),
+        )
+    }
+
+    /// Path of the directory
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Path of the public values of the proof, once verified locally
+    pub fn pub_input_path(&self) -> PathBuf {
+        self.path.join(PUB_INPUT_FILE)
+    }
+
+    /// Read the manifest, empty if no stage completed yet
+    ///
+    /// returns: Result<Manifest, Error>
+    pub fn manifest(&self) -> Result<Manifest> {
+        let path = self.path.join(MANIFEST_FILE);
+        if !path.exists() {
+            return Ok(Manifest::default());
+        }
+        serde_json::from_slice(&fs::read(&path)?)
+            .context(format!("Invalid manifest: {}", path.display()))
+    }
+
+    /// Delete the artifacts of every stage, to start the reveal over
+    ///
+    /// returns: Result<(), Error>
+    pub fn reset(&self) -> Result<()> {
+        fs::remove_dir_all(&self.path)?;
+        fs::create_dir_all(&self.path)?;
+        Ok(())
+    }
+
+    /// Save the proof created by `prove_auction`
+    ///
+    /// # Arguments
+    ///
+    /// * `auction_proof`: proof of the auctions
+    /// * `auctions`: data of the proven auctions, in the order they were proven
+    ///
+    /// returns: Result<(), Error>
+    pub fn save_proof(&self, auction_proof: &AuctionProof, auctions: &[AuctionData]) -> Result<()> {
+        self.write(PROOF_FILE, &bincode::serialize(&auction_proof.proof)?)?;
+        let mut manifest = self.manifest()?;
+        manifest.auction_ids = auctions
+            .iter()
+            .map(|auction_data| U256::from_big_endian(&auction_data.id))
+            .collect();
+        manifest.stage = Some(Stage::Proved);
+        self.save_manifest(&manifest)
+    }
+
+    /// Load the proof saved by `save_proof`
+    ///
+    /// returns: Result<AuctionProof, Error>
+    pub fn load_proof(&self) -> Result<AuctionProof> {
+        let (_, vk) = ProverClient::new().setup(get_elf()?.as_slice());
+        Ok(AuctionProof {
+            proof: self.read_proof()?,
+            vk,
+        })
+    }
+
+    /// Save the proof checked by `verify_local`
+    ///
+    /// # Arguments
+    ///
+    /// * `verified_proof`: proof verified locally
+    ///
+    /// returns: Result<(), Error>
+    pub fn save_verified(&self, verified_proof: &LocallyVerifiedProof) -> Result<()> {
+        self.write(
+            PUB_INPUT_FILE,
+            verified_proof.proof.public_values.as_slice(),
+        )?;
+        self.set_stage(Stage::VerifiedLocally)
+    }
+
+    /// Load the proof saved by `save_verified`
+    ///
+    /// returns: Result<LocallyVerifiedProof, Error>
+    pub fn load_verified(&self) -> Result<LocallyVerifiedProof> {
+        Ok(LocallyVerifiedProof {
+            proof: self.read_proof()?,
+            batch: AuctionPublicValues::decode_batch(&self.read(PUB_INPUT_FILE)?)?,
+        })
+    }
+
+    /// Save the proof submitted by `submit_to_aligned`
+    ///
+    /// # Arguments
+    ///
+    /// * `submitted_proof`: proof submitted to Aligned
+    ///
+    /// returns: Result<(), Error>
+    pub fn save_submitted(&self, submitted_proof: &SubmittedProof) -> Result<()> {
+        self.write(
+            ALIGNED_VERIFICATION_DATA_FILE,
+            &serde_json::to_vec(&submitted_proof.aligned_verification_data)?,
+        )?;
+        self.set_stage(Stage::Submitted)
+    }
+
+    /// Load the proof saved by `save_submitted`
+    ///
+    /// returns: Result<SubmittedProof, Error>
+    pub fn load_submitted(&self) -> Result<SubmittedProof> {
+        let pub_input = self.read(PUB_INPUT_FILE)?;
+        Ok(SubmittedProof {
+            batch: AuctionPublicValues::decode_batch(&pub_input)?,
+            pub_input,
+            aligned_verification_data: serde_json::from_slice(
+                &self.read(ALIGNED_VERIFICATION_DATA_FILE)?,
+            )?,
+        })
+    }
+
+    /// Save the proof verified on-chain by `wait_for_batch`, with its encoding for the contract
+    ///
+    /// # Arguments
+    ///
+    /// * `verified_proof`: proof encoded by `encode_verified_proof`
+    ///
+    /// returns: Result<(), Error>
+    pub fn save_batched(&self, verified_proof: &[u8]) -> Result<()> {
+        self.write(VERIFIED_PROOF_FILE, verified_proof)?;
+        self.set_stage(Stage::Batched)
+    }
+
+    /// Load the proof saved by `save_batched`
+    ///
+    /// returns: Result<(BatchedProof, Vec<u8, Global>), Error> (proof verified on-chain, verified
+    /// proof encoded for the contract)
+    pub fn load_batched(&self) -> Result<(BatchedProof, Vec<u8>)> {
+        let submitted_proof = self.load_submitted()?;
+        Ok((
+            BatchedProof {
+                batch: submitted_proof.batch,
+                pub_input: submitted_proof.pub_input,
+                aligned_verification_data: submitted_proof.aligned_verification_data,
+            },
+            self.read(VERIFIED_PROOF_FILE)?,
+        ))
+    }
+
+    /// Go back to the proof verified locally, so the next run submits it again instead of waiting
+    /// on a batch that is not verified
+    ///
+    /// returns: Result<(), Error>
+    pub fn discard_submission(&self) -> Result<()> {
+        if self.manifest()?.stage == Some(Stage::Submitted) {
+            self.set_stage(Stage::VerifiedLocally)?;
+        }
+        Ok(())
+    }
+
+    /// Check the saved progress belongs to the auctions to reveal, so a resume never uses the
+    /// proof of other auctions
+    ///
+    /// # Arguments
+    ///
+    /// * `auctions`: data of the auctions to reveal, the auctions finalized by a previous run
+    ///   excluded
+    ///
+    /// returns: Result<(), Error> Error if the saved proof does not prove exactly these auctions
+    /// in this order, or, once it is verified, does not prove all of them
+    pub fn check_auctions(&self, auctions: &[AuctionData]) -> Result<()> {
+        let manifest = self.manifest()?;
+        let auction_ids: Vec<U256> = auctions
+            .iter()
+            .map(|auction_data| U256::from_big_endian(&auction_data.id))
+            .collect();
+        let matches = match manifest.stage {
+            None => true,
+            // The proof is verified again against the auctions
+            Some(Stage::Proved) => manifest.auction_ids == auction_ids,
+            Some(_) => auction_ids
+                .iter()
+                .all(|auction_id| manifest.auction_ids.contains(auction_id)),
+        };
+        if !matches {
+            return Err(anyhow!(
+                "Progress saved in {} proves auctions {:?}, not {:?}",
+                self.path.display(),
+                manifest.auction_ids,
+                auction_ids
+            ));
+        }
+        Ok(())
+    }
+
+    /// Record that an auction was finalized on-chain with the proof
+    ///
+    /// # Arguments
+    ///
+    /// * `auction_id`: id of the finalized auction
+    ///
+    /// returns: Result<(), Error>
+    pub fn mark_finalized(&self, auction_id: U256) -> Result<()> {
+        let mut manifest = self.manifest()?;
+        if !manifest.finalized.contains(&auction_id) {
+            manifest.finalized.push(auction_id);
+        }
+        self.save_manifest(&manifest)
+    }
+
+    fn set_stage(&self, stage: Stage) -> Result<()> {
+        let mut manifest = self.manifest()?;
+        manifest.stage = Some(stage);
+        self.save_manifest(&manifest)
+    }
+
+    fn save_manifest(&self, manifest: &Manifest) -> Result<()> {
+        self.write(MANIFEST_FILE, &serde_json::to_vec_pretty(manifest)?)
+    }
+
+    fn read_proof(&self) -> Result<SP1ProofWithPublicValues> {
+        Ok(bincode::deserialize(&self.read(PROOF_FILE)?)?)
+    }
+
+    fn read(&self, name: &str) -> Result<Vec<u8>> {
+        let path = self.path.join(name);
+        fs::read(&path).map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))
+    }
+
+    /// Write through a temporary file, so a crash never leaves a truncated artifact
+    fn write(&self, name: &str, data: &[u8]) -> Result<()> {
+        let path = self.path.join(name);
+        let tmp_path = self.path.join(format!("{}.tmp", name));
+        fs::write(&tmp_path, data)?;
+        fs::rename(&tmp_path, &path)
+            .map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use aligned_sp1_prover::AuctionData;
+    use ethers::types::{Address, U256};
+
+    use crate::artifacts::{ArtifactDir, Manifest, Stage};
+
+    #[test]
+    fn test_manifest() {
+        let base = env::temp_dir().join("tahken-test-manifest");
+        let dir =
+            ArtifactDir::for_auctions(&base, &Address::zero(), &[U256::from(1), U256::from(2)])
+                .unwrap();
+        dir.reset().unwrap();
+        assert!(dir.path().ends_with("auction-1-2"));
+        assert_eq!(dir.manifest().unwrap(), Manifest::default());
+
+        dir.set_stage(Stage::Submitted).unwrap();
+        dir.mark_finalized(U256::from(2)).unwrap();
+        dir.mark_finalized(U256::from(2)).unwrap();
+        let manifest = dir.manifest().unwrap();
+        assert_eq!(manifest.stage, Some(Stage::Submitted));
+        assert_eq!(manifest.finalized, vec![U256::from(2)]);
+        assert!(manifest.stage >= Some(Stage::VerifiedLocally));
+        assert!(manifest.stage < Some(Stage::Batched));
+
+        // A batch not verified in time is submitted again
+        dir.discard_submission().unwrap();
+        assert_eq!(dir.manifest().unwrap().stage, Some(Stage::VerifiedLocally));
+
+        dir.reset().unwrap();
+        assert_eq!(dir.manifest().unwrap(), Manifest::default());
+        std::fs::remove_dir_all(base).unwrap();
+    }
+
+    #[test]
+    fn test_check_auctions() {
+        let base = env::temp_dir().join("tahken-test-check-auctions");
+        let dir =
+            ArtifactDir::for_auctions(&base, &Address::zero(), &[U256::from(1), U256::from(2)])
+                .unwrap();
+        dir.reset().unwrap();
+        let auctions = |ids: &[u64]| -> Vec<AuctionData> {
+            ids.iter()
+                .map(|id| {
+                    let mut auc_id = [0; 32];
+                    U256::from(*id).to_big_endian(&mut auc_id);
+                    AuctionData {
+                        id: auc_id.to_vec(),
+                        ..AuctionData::test_default()
+                    }
+                })
+                .collect()
+        };
+        dir.check_auctions(&auctions(&[3])).unwrap();
+
+        dir.save_manifest(&Manifest {
+            stage: Some(Stage::Proved),
+            auction_ids: vec![U256::from(1), U256::from(2)],
+            finalized: vec![],
+        })
+        .unwrap();
+        dir.check_auctions(&auctions(&[1, 2])).unwrap();
+        assert!(dir.check_auctions(&auctions(&[2, 1])).is_err());
+        assert!(dir.check_auctions(&auctions(&[1])).is_err());
+
+        // Once verified, the auctions finalized by a previous run are left out
+        dir.set_stage(Stage::Submitted).unwrap();
+        dir.check_auctions(&auctions(&[2])).unwrap();
+        assert!(dir.check_auctions(&auctions(&[2, 3])).is_err());
+        std::fs::remove_dir_all(base).unwrap();
+    }
+}
@@ -0,0 +1,8 @@
+use anyhow::Result;
+use prover_sdk::chunk_vk_digest;
+
+/// Print the digest of the verification key of the chunk program, included by the main program
+fn main() -> Result<()> {
+    println!("{:?}", chunk_vk_digest()?);
+    Ok(())
+}
@@ -0,0 +1,441 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use aligned_sp1_prover::calc_encryption_key_hash;
+use anyhow::{anyhow, Context, Result};
+use ecies::{PublicKey, SecretKey};
+use ethers::core::rand::rngs::OsRng;
+use ethers::signers::LocalWallet;
+
+/// Name of the key used when none is given
+pub const DEFAULT_KEY_NAME: &str = "default";
+
+const PUBLIC_KEY_EXTENSION: &str = "pub";
+const PRIVATE_KEY_EXTENSION: &str = "key";
+
+/// Encode a public key as stored on-chain by `create_new_auction`
+///
+/// # Arguments
+///
+/// * `pbk`: public key
+///
+/// returns: String 0x-prefixed hex of the uncompressed public key
+pub fn encode_public_key(pbk: &PublicKey) -> String {
+    format!("0x{}", hex::encode(pbk.serialize()))
+}
+
+/// Fingerprint of a public key, the hash bound to the proof of each auction using the key, see
+/// `calc_encryption_key_hash`
+///
+/// # Arguments
+///
+/// * `pbk`: public key
+///
+/// returns: String 0x-prefixed hex of the hash of the public key
+pub fn fingerprint(pbk: &PublicKey) -> String {
+    format!("0x{}", hex::encode(calc_encryption_key_hash(pbk)))
+}
+
+/// Directory of named encryption key pairs of auction owners. Each key is stored as
+/// `<name>.pub`, holding the hex-encoded serialized public key, and `<name>.key`, holding the
+/// private key encrypted with a password in the format of the Ethereum JSON keystore (scrypt and
+/// AES-128-CTR), like the keystore of the owner's wallet.
+pub struct KeyStore {
+    dir: PathBuf,
+}
+
+impl KeyStore {
+    /// Open the key store at `dir`, created when the first key is saved
+    ///
+    /// # Arguments
+    ///
+    /// * `dir`: directory of the keys
+    ///
+    /// returns: KeyStore
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        KeyStore { dir: dir.into() }
+    }
+
+    /// Per-user directory of the keys, `$XDG_DATA_HOME/tahken/keys` or
+    /// `~/.local/share/tahken/keys`
+    ///
+    /// returns: Result<PathBuf, Error>
+    pub fn default_dir() -> Result<PathBuf> {
+        let data_dir = match env::var_os("XDG_DATA_HOME") {
+            Some(data_dir) if !data_dir.is_empty() => PathBuf::from(data_dir),
+            _ => home::home_dir()
+                .ok_or(anyhow!("Failed to get home directory"))?
+                .join(".local/share"),
+        };
+        Ok(data_dir.join("tahken/keys"))
+    }
+
+    /// Directory of the keys
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Get a public encryption key
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: name of the key
+    ///
+    /// returns: Result<PublicKey, Error>
+    pub fn public_key(&self, name: &str) -> Result<PublicKey> {
+        let data = self.read(name, PUBLIC_KEY_EXTENSION)?;
+        PublicKey::parse_slice(&data, None)
+            .map_err(|e| anyhow!("Invalid public key {}: {}", name, e))
+    }
+
+    /// Unlock a private encryption key, checking it matches its public key
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: name of the key
+    /// * `password`: password encrypting the key
+    ///
+    /// returns: Result<SecretKey, Error>
+    pub fn private_key(&self, name: &str, password: &str) -> Result<SecretKey> {
+        let path = self.path(name, PRIVATE_KEY_EXTENSION)?;
+        if !is_encrypted(&path)? {
+            return Err(anyhow!(
+                "Private key {} is not encrypted, import it again with a password",
+                name
+            ));
+        }
+        let pvk = decrypt(&path, password)?;
+        if PublicKey::from_secret_key(&pvk) != self.public_key(name)? {
+            return Err(anyhow!(
+                "Private key {} does not match its public key",
+                name
+            ));
+        }
+        Ok(pvk)
+    }
+
+    /// Find the name of the key of a public key, e.g. the key of an auction, without unlocking it
+    ///
+    /// # Arguments
+    ///
+    /// * `pbk`: public key
+    ///
+    /// returns: Result<String, Error>
+    pub fn find_key(&self, pbk: &PublicKey) -> Result<String> {
+        for name in self.names()? {
+            if self.public_key(&name)? == *pbk {
+                return Ok(name);
+            }
+        }
+        Err(anyhow!(
+            "No key in {} matches the encryption key {}",
+            self.dir.display(),
+            encode_public_key(pbk)
+        ))
+    }
+
+    /// Generate a key pair under a new name
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: name of the key, made of letters, digits, `-` and `_`
+    /// * `password`: password encrypting the private key
+    ///
+    /// returns: Result<PublicKey, Error> generated public key
+    pub fn generate(&self, name: &str, password: &str) -> Result<PublicKey> {
+        let pvk = SecretKey::random(&mut OsRng);
+        self.save(name, &pvk, password)?;
+        Ok(PublicKey::from_secret_key(&pvk))
+    }
+
+    /// Import a private key under a new name, from a file holding either the hex-encoded key, like
+    /// the key files generated before the key store, or the key encrypted by `export`
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: name of the key, made of letters, digits, `-` and `_`
+    /// * `path`: file of the private key
+    /// * `password`: password of the encrypted file, also encrypting the imported key
+    ///
+    /// returns: Result<PublicKey, Error> public key of the imported key
+    pub fn import(&self, name: &str, path: impl AsRef<Path>, password: &str) -> Result<PublicKey> {
+        let path = path.as_ref();
+        let pvk = if is_encrypted(path)? {
+            decrypt(path, password)?
+        } else {
+            let content = fs::read_to_string(path)?;
+            let data = hex::decode(content.trim().trim_start_matches("0x"))
+                .context(format!("Invalid key file: {}", path.display()))?;
+            SecretKey::parse_slice(&data)
+                .map_err(|e| anyhow!("Invalid private key {}: {}", path.display(), e))?
+        };
+        self.save(name, &pvk, password)?;
+        Ok(PublicKey::from_secret_key(&pvk))
+    }
+
+    /// Export an encrypted private key, to import it in another key store with its password
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: name of the key
+    /// * `path`: file written, it must not exist
+    ///
+    /// returns: Result<(), Error>
+    pub fn export(&self, name: &str, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let key_path = self.path(name, PRIVATE_KEY_EXTENSION)?;
+        if !is_encrypted(&key_path)? {
+            return Err(anyhow!(
+                "Private key {} is not encrypted, import it again with a password",
+                name
+            ));
+        }
+        if path.exists() {
+            return Err(anyhow!("{} already exists", path.display()));
+        }
+        fs::copy(&key_path, path)?;
+        Ok(())
+    }
+
+    /// Save a key pair under a new name
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: name of the key, made of letters, digits, `-` and `_`
+    /// * `pvk`: private key, its public key is saved next to it
+    /// * `password`: password encrypting the private key
+    ///
+    /// returns: Result<(), Error>
+    pub fn save(&self, name: &str, pvk: &SecretKey, password: &str) -> Result<()> {
+        if self.contains(name)? {
+            return Err(anyhow!("Key {} already exists", name));
+        }
+        create_private_dir(&self.dir)?;
+        let path = self.path(name, PRIVATE_KEY_EXTENSION)?;
+        // The keystore is written with the default permissions, so it is written in a directory
+        // only the owner can open and moved next to the other keys once only the owner can read it
+        let tmp_dir = self.dir.join(format!(".{}.tmp", name));
+        if tmp_dir.exists() {
+            fs::remove_dir_all(&tmp_dir)?;
+        }
+        create_private_dir(&tmp_dir)?;
+        let saved = LocalWallet::encrypt_keystore(
+            &tmp_dir,
+            &mut OsRng,
+            pvk.serialize(),
+            password,
+            Some(PRIVATE_KEY_EXTENSION),
+        )
+        .map_err(|e| anyhow!("Failed to encrypt key {}: {}", name, e))
+        .and_then(|_| {
+            let tmp_path = tmp_dir.join(PRIVATE_KEY_EXTENSION);
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                fs::set_permissions(&tmp_path, fs::Permissions::from_mode(0o600))?;
+            }
+            fs::rename(&tmp_path, &path).context(format!(
+                "Failed to save key {} to {}",
+                name,
+                path.display()
+            ))
+        });
+        fs::remove_dir_all(&tmp_dir)?;
+        saved?;
+        let path = self.path(name, PUBLIC_KEY_EXTENSION)?;
+        fs::write(
+            &path,
+            hex::encode(PublicKey::from_secret_key(pvk).serialize()),
+        )?;
+        Ok(())
+    }
+
+    /// Whether a key is stored under `name`
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: name of the key
+    ///
+    /// returns: Result<bool, Error>
+    pub fn contains(&self, name: &str) -> Result<bool> {
+        Ok(self.path(name, PUBLIC_KEY_EXTENSION)?.exists()
+            || self.path(name, PRIVATE_KEY_EXTENSION)?.exists())
+    }
+
+    /// Names of the stored keys, sorted
+    ///
+    /// returns: Result<Vec<String, Global>, Error>
+    pub fn names(&self) -> Result<Vec<String>> {
+        if !self.dir.exists() {
+            return Ok(vec![]);
+        }
+        let mut names = vec![];
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path
+                .extension()
+                .is_some_and(|ext| ext == PUBLIC_KEY_EXTENSION)
+            {
+                if let Some(name) = path.file_stem().and_then(|name| name.to_str()) {
+                    names.push(name.to_string());
+                }
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    fn path(&self, name: &str, extension: &str) -> Result<PathBuf> {
+        // Names are file names, they can not escape the directory
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(anyhow!("Invalid key name: {:?}", name));
+        }
+        Ok(self.dir.join(format!("{}.{}", name, extension)))
+    }
+
+    fn read(&self, name: &str, extension: &str) -> Result<Vec<u8>> {
+        let path = self.path(name, extension)?;
+        let content = fs::read_to_string(&path).context(format!(
+            "Failed to read key {} from {}",
+            name,
+            path.display()
+        ))?;
+        hex::decode(content.trim()).context(format!("Invalid key file: {}", path.display()))
+    }
+}
+
+/// Create a directory only the owner can open, with its parents
+fn create_private_dir(path: &Path) -> Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder.create(path).context(format!(
+        "Failed to create key directory: {}",
+        path.display()
+    ))
+}
+
+/// Whether a key file is an encrypted keystore rather than the hex-encoded key
+fn is_encrypted(path: &Path) -> Result<bool> {
+    let content = fs::read(path).context(format!("Failed to read key from {}", path.display()))?;
+    Ok(content.first() == Some(&b'{'))
+}
+
+fn decrypt(path: &Path, password: &str) -> Result<SecretKey> {
+    let wallet = LocalWallet::decrypt_keystore(path, password)
+        .map_err(|e| anyhow!("Failed to unlock key {}: {}", path.display(), e))?;
+    SecretKey::parse_slice(&wallet.signer().to_bytes())
+        .map_err(|e| anyhow!("Invalid private key {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+
+    use ecies::{PublicKey, SecretKey};
+    use ethers::core::rand::rngs::OsRng;
+    use ethers::types::Bytes;
+
+    use crate::keys::{encode_public_key, fingerprint, KeyStore};
+
+    #[test]
+    fn test_key_store() {
+        let dir = env::temp_dir().join("tahken-test-key-store");
+        let _ = fs::remove_dir_all(&dir);
+        let key_store = KeyStore::new(&dir);
+        assert!(key_store.names().unwrap().is_empty());
+
+        let pvk = SecretKey::random(&mut OsRng);
+        let other_pvk = SecretKey::random(&mut OsRng);
+        key_store.save("owner", &pvk, "password").unwrap();
+        key_store.save("other", &other_pvk, "other").unwrap();
+        assert!(key_store.save("owner", &other_pvk, "password").is_err());
+        assert_eq!(key_store.names().unwrap(), vec!["other", "owner"]);
+
+        // Only the owner can read the private key, and only with its password
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(dir.join("owner.key"))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        let content = fs::read_to_string(dir.join("owner.key")).unwrap();
+        assert!(!content.contains(&hex::encode(pvk.serialize())));
+        assert_eq!(key_store.private_key("owner", "password").unwrap(), pvk);
+        assert!(key_store.private_key("owner", "other").is_err());
+        assert_eq!(
+            key_store.public_key("owner").unwrap(),
+            PublicKey::from_secret_key(&pvk)
+        );
+        assert_eq!(
+            key_store
+                .find_key(&PublicKey::from_secret_key(&other_pvk))
+                .unwrap(),
+            "other"
+        );
+
+        // Keys saved before encryption are refused
+        fs::write(dir.join("plain.key"), hex::encode(pvk.serialize())).unwrap();
+        fs::write(
+            dir.join("plain.pub"),
+            hex::encode(PublicKey::from_secret_key(&pvk).serialize()),
+        )
+        .unwrap();
+        assert!(key_store.private_key("plain", "password").is_err());
+
+        // Names can not point outside the directory
+        assert!(key_store.public_key("../owner").is_err());
+        assert!(key_store.public_key("missing").is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_import_export() {
+        let dir = env::temp_dir().join("tahken-test-key-import");
+        let _ = fs::remove_dir_all(&dir);
+        let key_store = KeyStore::new(dir.join("keys"));
+        let other_key_store = KeyStore::new(dir.join("other-keys"));
+
+        let pbk = key_store.generate("owner", "password").unwrap();
+        key_store.export("owner", dir.join("owner.json")).unwrap();
+        assert!(key_store.export("owner", dir.join("owner.json")).is_err());
+        assert!(other_key_store
+            .import("owner", dir.join("owner.json"), "wrong")
+            .is_err());
+        assert_eq!(
+            other_key_store
+                .import("owner", dir.join("owner.json"), "password")
+                .unwrap(),
+            pbk
+        );
+
+        // Hex-encoded keys are encrypted when imported
+        let pvk = SecretKey::random(&mut OsRng);
+        fs::write(dir.join("plain"), hex::encode(pvk.serialize())).unwrap();
+        key_store
+            .import("plain", dir.join("plain"), "password")
+            .unwrap();
+        assert_eq!(key_store.private_key("plain", "password").unwrap(), pvk);
+
+        // Same format as the key sent on-chain
+        assert_eq!(
+            encode_public_key(&pbk),
+            Bytes::from(pbk.serialize()).to_string()
+        );
+        assert_eq!(fingerprint(&pbk).len(), 66);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
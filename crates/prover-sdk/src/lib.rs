@@ -1,22 +1,32 @@
 extern crate core;
 
-use std::fs;
 use std::fs::File;
 use std::io::Read;
 use std::path::PathBuf;
 
-use aligned_sdk::core::types::{Network, PriceEstimate, ProvingSystemId, VerificationData};
-use aligned_sdk::sdk::{estimate_fee, get_next_nonce, submit_and_wait_verification};
-use aligned_sp1_prover::AuctionData;
-use anyhow::{anyhow, Result};
+use aligned_sdk::core::types::{Network, PriceEstimate};
+use aligned_sdk::sdk::estimate_fee;
+use aligned_sp1_prover::{
+    encode_bid, find_winner, AuctionData, AuctionOutcome, AuctionPublicValues, CipherScheme,
+};
+use anyhow::{anyhow, Context, Result};
 use dialoguer::Confirm;
 use ecies::{PublicKey, SecretKey};
-use ethers::abi::{encode, Token, Uint};
 use ethers::core::k256::ecdsa::SigningKey;
-use ethers::prelude::Signer;
 use ethers::signers::Wallet;
 use ethers::types::{Address, U256};
-use sp1_sdk::{ProverClient, SP1Stdin};
+use sp1_sdk::{HashableKey, ProverClient};
+
+pub use crate::artifacts::{ArtifactDir, Manifest, Stage};
+pub use crate::keys::{KeyStore, DEFAULT_KEY_NAME};
+pub use crate::pipeline::{
+    encode_verified_proof, prove_auction, submit_to_aligned, verify_local, wait_for_batch,
+    AuctionProof, BatchedProof, LocallyVerifiedProof, SubmittedProof,
+};
+
+mod artifacts;
+pub mod keys;
+mod pipeline;
 
 /// Return winner and proof for the function `revealWinner` in the contract
 ///
@@ -24,163 +34,194 @@ use sp1_sdk::{ProverClient, SP1Stdin};
 ///
 /// * `wallet`: wallet of the owner
 /// * `auction_data`: data of the auction
+/// * `unlock_key`: unlocks the owner's private key of the encryption key stored in the auction,
+///   see `get_winners_and_submit_proof`
 /// * `rpc_url`: rpc url of the network
 /// * `network`: network supported by Aligned
 /// * `batcher_url`: Aligned batcher URL
+/// * `chunk_size`: number of bids decrypted by each chunk proof, `None` to prove every bid at once
+/// * `artifact_dir`: directory saving the artifact of each stage, see
+///   `get_winners_and_submit_proof`
 ///
-/// returns: Result<(H160, u128, Vec<u8, Global>), Error> (winner address, winner amount, verified proof)
+/// returns: Result<(AuctionPublicValues, Vec<u8, Global>), Error> (public values of the proof,
+/// verified proof).
+/// The list of winners is empty when no valid bid reaches the reserve price.
+/// Auctions without any bid are rejected: they are ended without a proof.
+#[allow(clippy::too_many_arguments)]
 pub async fn get_winner_and_submit_proof(
     wallet: Wallet<SigningKey>,
     auction_data: &AuctionData,
+    unlock_key: impl FnOnce() -> Result<SecretKey>,
     rpc_url: &str,
     network: Network,
     batcher_url: &str,
-) -> Result<(Address, u128, Vec<u8>)> {
-    println!("Creating proof...");
-    
-    let mut stdin = SP1Stdin::new();
-    stdin.write(auction_data);
-    stdin.write(&get_private_encryption_key()?.serialize().to_vec());
-
-    let client = ProverClient::new();
-    let (pk, vk) = client.setup(get_elf()?.as_slice());
-
-    let mut proof = client.prove(&pk, stdin).compressed().run()?;
-    println!("Proof created successfully");
-
-    client.verify(&proof, &vk)?;
-
-    let pub_input = proof.public_values.to_vec();
-    let _hash_data = proof.public_values.read::<[u8; 32]>().to_vec(); // hash(auctionData)
-    let winner_addr = Address::from_slice(proof.public_values.read::<Vec<u8>>().as_slice()); // winner address
-    let winner_amount = proof.public_values.read::<u128>(); // winner amount
-
-    let proof = bincode::serialize(&proof).expect("Failed to serialize proof");
-
-    fs::write("proof", &proof).expect("Failed to write proof to file");
-    fs::write("pub_input", &pub_input).expect("Failed to write pub_input to file");
-    let verification_data = VerificationData {
-        proving_system: ProvingSystemId::SP1,
-        proof,
-        proof_generator_addr: wallet.address(),
-        vm_program_code: Some(get_elf()?),
-        verification_key: None,
-        pub_input: Some(pub_input.clone()),
-    };
-    let max_fee = estimate_fee(rpc_url, PriceEstimate::Instant)
-        .await
-        .expect("failed to fetch gas price from the blockchain");
-
-    #[cfg(not(test))]
-    let max_fee_string = ethers::utils::format_units(max_fee, 18)?;
+    chunk_size: Option<usize>,
+    artifact_dir: &ArtifactDir,
+) -> Result<(AuctionPublicValues, Vec<u8>)> {
+    let (mut batch, verified_proof) = get_winners_and_submit_proof(
+        wallet,
+        std::slice::from_ref(auction_data),
+        unlock_key,
+        rpc_url,
+        network,
+        batcher_url,
+        chunk_size,
+        artifact_dir,
+    )
+    .await?;
+    Ok((batch.remove(0), verified_proof))
+}
 
-    #[cfg(not(test))]
-    if !Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
-        .with_prompt(format!("Aligned will use at most {max_fee_string} eth to verify your proof. Do you want to continue?"))
-        .interact()
-        .expect("Failed to read user input") {
-        return Err(anyhow!(""))
+/// Return the winners of several auctions and one shared proof for the function `revealWinner`
+/// of each auction in the contract, paying a single Aligned fee. Runs every stage of the
+/// pipeline, asking for confirmation of the fee before submitting to Aligned. The artifact of each
+/// stage is saved in `artifact_dir`, and the stages completed by a previous run are skipped.
+///
+/// # Arguments
+///
+/// * `wallet`: wallet of the owner
+/// * `auctions`: data of each auction, all encrypted with the same key
+/// * `unlock_key`: unlocks the owner's private key of the encryption key stored in every auction,
+///   only called when the proof is not created and verified yet, e.g. to ask for its password
+/// * `rpc_url`: rpc url of the network
+/// * `network`: network supported by Aligned
+/// * `batcher_url`: Aligned batcher URL
+/// * `chunk_size`: number of bids decrypted by each chunk proof, `None` to prove every bid at once.
+///   The chunk proofs are aggregated in a proof with the same public values.
+/// * `artifact_dir`: directory saving the artifact of each stage, `auctions` are only read by the
+///   stages that did not complete yet. It must prove `auctions`, and goes back to the verified
+///   proof if its batch is not verified in time, so the next run submits it again
+///
+/// returns: Result<(Vec<AuctionPublicValues, Global>, Vec<u8, Global>), Error> (public values of
+/// each auction in the order of `auctions`, verified proof shared by every auction).
+/// Auctions without any bid are rejected: they are ended without a proof.
+#[allow(clippy::too_many_arguments)]
+pub async fn get_winners_and_submit_proof(
+    wallet: Wallet<SigningKey>,
+    auctions: &[AuctionData],
+    unlock_key: impl FnOnce() -> Result<SecretKey>,
+    rpc_url: &str,
+    network: Network,
+    batcher_url: &str,
+    chunk_size: Option<usize>,
+    artifact_dir: &ArtifactDir,
+) -> Result<(Vec<AuctionPublicValues>, Vec<u8>)> {
+    // Skip the stages completed by a previous run
+    artifact_dir.check_auctions(auctions)?;
+    let stage = artifact_dir.manifest()?.stage;
+    if stage >= Some(Stage::Batched) {
+        println!("Resuming from the proof verified on-chain...");
+        let (batched_proof, verified_proof) = artifact_dir.load_batched()?;
+        return Ok((batched_proof.batch, verified_proof));
     }
 
-    let nonce = get_next_nonce(rpc_url, wallet.address(), network)
-        .await
-        .expect("Failed to get next nonce");
+    let submitted_proof = if stage >= Some(Stage::Submitted) {
+        println!("Resuming from the proof submitted to Aligned...");
+        artifact_dir.load_submitted()?
+    } else {
+        let verified_proof = if stage >= Some(Stage::VerifiedLocally) {
+            println!("Resuming from the verified proof...");
+            artifact_dir.load_verified()?
+        } else {
+            let private_encryption_key = unlock_key()?;
+            let auction_proof = if stage >= Some(Stage::Proved) {
+                println!("Resuming from the created proof...");
+                artifact_dir.load_proof()?
+            } else {
+                let auction_proof = prove_auction(auctions, &private_encryption_key, chunk_size)?;
+                artifact_dir.save_proof(&auction_proof, auctions)?;
+                auction_proof
+            };
+            let verified_proof = verify_local(auction_proof, auctions, &private_encryption_key)?;
+            artifact_dir.save_verified(&verified_proof)?;
+            verified_proof
+        };
 
-    println!("Submitting your proof...");
+        let max_fee = estimate_fee(rpc_url, PriceEstimate::Instant)
+            .await
+            .expect("failed to fetch gas price from the blockchain");
 
-    let aligned_verification_data = submit_and_wait_verification(
-        batcher_url,
-        rpc_url,
-        network,
-        &verification_data,
-        max_fee,
-        wallet,
-        nonce,
-    )
-    .await
-    .unwrap();
-
-    println!(
-        "Proof submitted and verified successfully on batch {}",
-        hex::encode(aligned_verification_data.batch_merkle_root)
-    );
-
-    let mut index_in_batch = [0; 32];
-    U256::from(aligned_verification_data.index_in_batch).to_big_endian(&mut index_in_batch);
-
-    let merkle_path: Vec<u8> = flatten(
-        aligned_verification_data
-            .batch_inclusion_proof
-            .merkle_path
-            .as_slice(),
-    );
-
-    let verified_proof = encode(&[
-        Token::Bytes(pub_input),
-        Token::FixedBytes(
-            aligned_verification_data
-                .verification_data_commitment
-                .proof_commitment
-                .to_vec(),
-        ),
-        Token::FixedBytes(
-            aligned_verification_data
-                .verification_data_commitment
-                .pub_input_commitment
-                .to_vec(),
-        ),
-        Token::FixedBytes(
-            aligned_verification_data
-                .verification_data_commitment
-                .proving_system_aux_data_commitment
-                .to_vec(),
-        ),
-        Token::FixedBytes(
-            aligned_verification_data
-                .verification_data_commitment
-                .proof_generator_addr
-                .to_vec(),
-        ),
-        Token::FixedBytes(aligned_verification_data.batch_merkle_root.to_vec()),
-        Token::Bytes(merkle_path),
-        Token::Uint(Uint::from(index_in_batch)),
-    ]);
-
-    fs::write("verified_proof", &verified_proof).expect("Failed to write verified proof to file");
-
-    Ok((winner_addr, winner_amount, verified_proof))
+        #[cfg(not(test))]
+        let max_fee_string = ethers::utils::format_units(max_fee, 18)?;
+
+        #[cfg(not(test))]
+        if !Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt(format!("Aligned will use at most {max_fee_string} eth to verify your proof. Do you want to continue?"))
+            .interact()
+            .expect("Failed to read user input") {
+            return Err(anyhow::anyhow!(""))
+        }
+
+        let submitted_proof = submit_to_aligned(
+            wallet,
+            verified_proof,
+            rpc_url,
+            network,
+            batcher_url,
+            max_fee,
+        )
+        .await?;
+        artifact_dir.save_submitted(&submitted_proof)?;
+        submitted_proof
+    };
+
+    let Some(batched_proof) = wait_for_batch(submitted_proof, rpc_url, network).await? else {
+        // The batch may never be verified, e.g. if Aligned dropped the proof
+        artifact_dir.discard_submission()?;
+        return Err(anyhow!(
+            "Proof is not verified on-chain yet, resume the reveal to submit it again"
+        ));
+    };
+    let verified_proof = encode_verified_proof(&batched_proof);
+    artifact_dir.save_batched(&verified_proof)?;
+
+    Ok((batched_proof.batch, verified_proof))
+}
+
+/// Compute the outcome of the auction locally, without generating a proof
+///
+/// # Arguments
+///
+/// * `auction_data`: data of the auction
+/// * `private_encryption_key`: owner's private key of the encryption key stored in the auction
+///
+/// returns: AuctionOutcome Winner, winning bid, clearing price and rejected bids
+pub fn preview_winner(
+    auction_data: &AuctionData,
+    private_encryption_key: &SecretKey,
+) -> AuctionOutcome {
+    find_winner(auction_data, private_encryption_key)
 }
 
-/// Encrypts the amount of a bidder using the public key of the owner
+/// Encrypts the amount of a bidder using the public key of the owner.
+/// The amount is bound to the bidder and the auction, so the ciphertext can not be reused by
+/// another bidder.
 ///
 /// # Arguments
 ///
 /// * `amount`: bid amount
+/// * `bidder`: address of the bidder
+/// * `auction_id`: id of the auction
 /// * `pbk`: public key of the owner
+/// * `cipher_scheme`: scheme chosen at auction creation
 ///
 /// returns: Vec<u8, Global> encrypted amount
-pub fn encrypt_bidder_amount(amount: &u128, pbk: &PublicKey) -> Vec<u8> {
-    ecies::encrypt(&pbk.serialize(), &amount.to_be_bytes()).expect("failed to encrypt bidder data")
-}
-
-/// Get the public encryption key of the owner
-pub fn get_encryption_key() -> Result<PublicKey> {
-    Ok(PublicKey::parse(
-        &hex::decode(fs::read_to_string(
-            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../sp1-prover/encryption_key"),
-        )?)?
-        .try_into()
-        .unwrap(),
-    )
-    .expect("parsing public encryption key failed"))
-}
-
-/// Get the private encryption key of the owner
-pub fn get_private_encryption_key() -> Result<SecretKey> {
-    Ok(SecretKey::parse_slice(&hex::decode(fs::read_to_string(
-        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../sp1-prover/private_encryption_key"),
-    )?)?)
-    .expect("parsing private encryption key failed"))
+pub fn encrypt_bidder_amount(
+    amount: &u128,
+    bidder: &Address,
+    auction_id: &U256,
+    pbk: &PublicKey,
+    cipher_scheme: CipherScheme,
+) -> Vec<u8> {
+    let mut id = [0; 32];
+    auction_id.to_big_endian(&mut id);
+    cipher_scheme
+        .cipher()
+        .encrypt(
+            &pbk.serialize(),
+            &encode_bid(*amount, bidder.as_bytes(), &id),
+        )
+        .expect("failed to encrypt bidder data")
 }
 
 /// Get the ELF file that was compiled with the SP1 prover
@@ -194,6 +235,22 @@ pub fn get_elf() -> Result<Vec<u8>> {
     Ok(buffer)
 }
 
+/// Get the ELF file of the chunk program that was compiled with the SP1 prover
+pub fn get_chunk_elf() -> Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    File::open(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../sp1-prover/elf/chunk-elf"))
+        .context("Missing ELF of the chunk program, run `make chunk-elf` in crates/sp1-prover")?
+        .read_to_end(&mut buffer)?;
+    Ok(buffer)
+}
+
+/// Get the digest of the verification key of the chunk program, included in the main program so
+/// it only aggregates proofs of the chunk program
+pub fn chunk_vk_digest() -> Result<[u32; 8]> {
+    let (_, vk) = ProverClient::new().setup(get_chunk_elf()?.as_slice());
+    Ok(vk.hash_u32())
+}
+
 /// Flatten a 2D array into a 1D array
 ///
 /// # Arguments
@@ -212,18 +269,23 @@ pub fn flatten(vec: &[[u8; 32]]) -> Vec<u8> {
 #[cfg(test)]
 mod tests {
     use std::env;
+    use std::fs;
     use std::fs::File;
     use std::io::Read;
     use std::str::FromStr;
 
-    use aligned_sdk::core::types::Network;
-    use aligned_sp1_prover::{AuctionData, Bidder};
+    use aligned_sdk::core::types::{Network, ProvingSystemId};
+    use aligned_sp1_prover::{
+        AuctionData, AuctionPublicValues, Bidder, CipherScheme, ProgramInput,
+    };
+    use ecies::{PublicKey, SecretKey};
     use ethers::prelude::Signer;
     use ethers::signers::LocalWallet;
-    use ethers::types::{Bytes, H160};
+    use ethers::types::{Bytes, H160, U256};
+    use ethers::utils::keccak256;
     use sp1_sdk::{ProverClient, SP1Stdin};
 
-    use crate::{encrypt_bidder_amount, get_encryption_key, get_private_encryption_key};
+    use crate::{encrypt_bidder_amount, preview_winner, ArtifactDir, KeyStore, DEFAULT_KEY_NAME};
 
     #[tokio::test]
     async fn test_submit_proof() {
@@ -234,21 +296,26 @@ mod tests {
             .unwrap()
             .with_chain_id(17000u64);
 
-        let (_winner_addr, winner_amount, _verified_proof) = super::get_winner_and_submit_proof(
+        let (public_values, _verified_proof) = super::get_winner_and_submit_proof(
             wallet,
             &auction_data(),
+            || Ok(private_encryption_key()),
             rpc_url,
             network,
             batcher_url,
+            None,
+            &ArtifactDir::new(env::temp_dir().join("tahken-test-submit-proof")).unwrap(),
         )
         .await
         .unwrap();
-        dbg!(winner_amount);
+        dbg!(public_values);
     }
 
     #[test]
     fn test_sp1_prover() {
-        // find_winner(&auction_data(), PrivateKey::from_bytes(hex::decode(ENCRYPTION_PRIVATE_KEY).unwrap()));
+        let auction_data = auction_data();
+        let pvk = private_encryption_key();
+        let outcome = preview_winner(&auction_data, &pvk);
         let elf = {
             let mut buffer = Vec::new();
             File::open("../sp1-prover/elf/riscv32im-succinct-zkvm-elf")
@@ -259,14 +326,14 @@ mod tests {
         };
 
         let mut stdin = SP1Stdin::new();
-        stdin.write(&auction_data());
-        stdin.write(&get_private_encryption_key().unwrap().serialize().to_vec());
+        stdin.write(&std::slice::from_ref(&auction_data));
+        stdin.write(&ProgramInput::PrivateKey(pvk.serialize().to_vec()));
 
         let client = ProverClient::new();
         let (pk, vk) = client.setup(elf.as_slice());
 
         println!("Generating proof...");
-        let Ok(mut proof) = client.prove(&pk, stdin).compressed().run() else {
+        let Ok(proof) = client.prove(&pk, stdin).compressed().run() else {
             println!("Something went wrong!");
             return;
         };
@@ -275,12 +342,25 @@ mod tests {
         client.verify(&proof, &vk).expect("verification failed");
         println!("Proof verified successfully.");
 
-        // println!("{:?}", proof.public_values);
-        let hash_data = proof.public_values.read::<[u8; 32]>();
-        println!("{:?}", hash_data);
-        let winner_addr = proof.public_values.read::<Vec<u8>>();
-        println!("{:?}", winner_addr);
-        // Todo: validate with data
+        let batch = AuctionPublicValues::decode_batch(proof.public_values.as_slice()).unwrap();
+        println!("{:?}", batch);
+        assert_eq!(
+            batch,
+            vec![outcome.public_values(&auction_data, &PublicKey::from_secret_key(&pvk))]
+        );
+    }
+
+    #[test]
+    fn test_elf_commitment() {
+        // The contract only accepts proofs of the committed ELF, see `make elf-commit`. The
+        // commitment is recomputed from the ELF, as `aligned get-vk-commitment` does, so a rebuilt
+        // ELF without a new commitment fails here.
+        let elf = fs::read("../sp1-prover/elf/riscv32im-succinct-zkvm-elf").unwrap();
+        let commitment = hex::encode(keccak256([elf, vec![ProvingSystemId::SP1 as u8]].concat()));
+        let committed = fs::read_to_string("../sp1-prover/elf/elf_commitment").unwrap();
+        assert_eq!(committed.trim(), commitment);
+        let contract = fs::read_to_string("../cli/contracts/src/ZkAuction.sol").unwrap();
+        assert!(contract.contains(&format!("ELF_COMMITMENT = 0x{};", commitment)));
     }
 
     #[test]
@@ -295,21 +375,50 @@ mod tests {
         assert_eq!(y.to_vec(), vec![1, 2, 3]);
     }
 
+    /// Default key of the per-user key store
+    fn key_store() -> KeyStore {
+        KeyStore::new(KeyStore::default_dir().unwrap())
+    }
+
+    fn private_encryption_key() -> SecretKey {
+        key_store()
+            .private_key(
+                DEFAULT_KEY_NAME,
+                &env::var("ENCRYPTION_KEY_PASSWORD").unwrap(),
+            )
+            .unwrap()
+    }
+
     fn auction_data() -> AuctionData {
-        let pbk = get_encryption_key().unwrap();
+        let pbk = key_store().public_key(DEFAULT_KEY_NAME).unwrap();
+        let bidder = H160::from_str("0xeDe4C2b4BdBE580750a99F016b0A1581C3808FA3").unwrap();
+        let auction_id = U256::zero();
 
         AuctionData {
             bidders: vec![
                 Bidder {
-                    encrypted_amount: encrypt_bidder_amount(&3, &pbk),
-                    address: hex::decode("eDe4C2b4BdBE580750a99F016b0A1581C3808FA3").unwrap(),
+                    encrypted_amount: encrypt_bidder_amount(
+                        &3,
+                        &bidder,
+                        &auction_id,
+                        &pbk,
+                        CipherScheme::Ecies,
+                    ),
+                    address: bidder.as_bytes().to_vec(),
                 },
                 Bidder {
-                    encrypted_amount: encrypt_bidder_amount(&2, &pbk),
-                    address: hex::decode("eDe4C2b4BdBE580750a99F016b0A1581C3808FA3").unwrap(),
+                    encrypted_amount: encrypt_bidder_amount(
+                        &2,
+                        &bidder,
+                        &auction_id,
+                        &pbk,
+                        CipherScheme::Ecies,
+                    ),
+                    address: bidder.as_bytes().to_vec(),
                 },
             ],
-            id: vec![0; 32],
+            contract_address: hex::decode("666fdd363f32e3b0efac35739aa9e2ee52a4b50e").unwrap(),
+            ..AuctionData::test_default()
         }
     }
 }
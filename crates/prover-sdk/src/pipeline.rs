@@ -0,0 +1,363 @@
+use std::time::Duration;
+
+use aligned_sdk::core::types::{
+    AlignedVerificationData, Network, ProvingSystemId, VerificationData,
+};
+use aligned_sdk::sdk::{get_next_nonce, is_proof_verified, submit};
+use aligned_sp1_prover::{
+    calc_encryption_key_hash, find_winner, AuctionData, AuctionPublicValues, ProgramInput,
+};
+use anyhow::{anyhow, Result};
+use ecies::{PublicKey, SecretKey};
+use ethers::abi::{encode, Token, Uint};
+use ethers::core::k256::ecdsa::SigningKey;
+use ethers::prelude::Signer;
+use ethers::signers::Wallet;
+use ethers::types::U256;
+use sp1_sdk::{ProverClient, SP1Proof, SP1ProofWithPublicValues, SP1Stdin, SP1VerifyingKey};
+
+use crate::{flatten, get_chunk_elf, get_elf};
+
+/// Delay between two checks of the verification of the batch on-chain
+const BATCH_POLL_INTERVAL: Duration = Duration::from_secs(10);
+/// Number of checks before giving up on the verification of the batch
+const BATCH_POLL_ATTEMPTS: u32 = 60;
+
+/// Proof of the outcome of several auctions, created by `prove_auction`
+pub struct AuctionProof {
+    /// Compressed proof of the main program
+    pub proof: SP1ProofWithPublicValues,
+    /// Verification key of the main program
+    pub vk: SP1VerifyingKey,
+}
+
+/// Proof checked against the outcome computed natively, created by `verify_local`
+pub struct LocallyVerifiedProof {
+    /// Compressed proof of the main program
+    pub proof: SP1ProofWithPublicValues,
+    /// Public values of each auction, in the order of the proven auctions
+    pub batch: Vec<AuctionPublicValues>,
+}
+
+/// Proof accepted in a batch by the Aligned batcher, created by `submit_to_aligned`
+pub struct SubmittedProof {
+    /// Public values of each auction, in the order of the proven auctions
+    pub batch: Vec<AuctionPublicValues>,
+    /// Public values committed by the proof
+    pub pub_input: Vec<u8>,
+    /// Commitments and inclusion proof of the proof in the batch
+    pub aligned_verification_data: AlignedVerificationData,
+}
+
+/// Proof whose batch is verified by Aligned on-chain, created by `wait_for_batch`
+pub struct BatchedProof {
+    /// Public values of each auction, in the order of the proven auctions
+    pub batch: Vec<AuctionPublicValues>,
+    /// Public values committed by the proof
+    pub pub_input: Vec<u8>,
+    /// Commitments and inclusion proof of the proof in the batch
+    pub aligned_verification_data: AlignedVerificationData,
+}
+
+/// Prove the outcome of several auctions in one run of the main program
+///
+/// # Arguments
+///
+/// * `auctions`: data of each auction, all encrypted with the same key
+/// * `private_encryption_key`: owner's private key of the encryption key stored in every auction
+/// * `chunk_size`: number of bids decrypted by each chunk proof, `None` to prove every bid at once.
+///   The chunk proofs are aggregated in a proof with the same public values.
+///
+/// returns: Result<AuctionProof, Error> Compressed proof of the outcome of every auction.
+/// Auctions without any bid are rejected: they are ended without a proof.
+pub fn prove_auction(
+    auctions: &[AuctionData],
+    private_encryption_key: &SecretKey,
+    chunk_size: Option<usize>,
+) -> Result<AuctionProof> {
+    if auctions.is_empty() {
+        return Err(anyhow!("No auction to prove"));
+    }
+    if auctions
+        .iter()
+        .any(|auction_data| auction_data.bidders.is_empty())
+    {
+        return Err(anyhow!("Auction has no bids, there is no winner to prove"));
+    }
+    // The zkVM program rejects the same auctions, fail before paying for the proof
+    for auction_data in auctions {
+        auction_data.validate()?;
+    }
+    println!("Creating proof...");
+
+    let client = ProverClient::new();
+    let mut stdin = SP1Stdin::new();
+    stdin.write(&auctions);
+    match chunk_size {
+        None => stdin.write(&ProgramInput::PrivateKey(
+            private_encryption_key.serialize().to_vec(),
+        )),
+        Some(chunk_size) => prove_chunks(
+            &client,
+            &mut stdin,
+            auctions,
+            private_encryption_key,
+            chunk_size,
+        )?,
+    }
+
+    let (pk, vk) = client.setup(get_elf()?.as_slice());
+
+    let proof = client.prove(&pk, stdin).compressed().run()?;
+    println!("Proof created successfully");
+
+    Ok(AuctionProof { proof, vk })
+}
+
+/// Verify a proof locally and check it commits the outcome computed natively, so a wrong proof
+/// is not paid for on Aligned
+///
+/// # Arguments
+///
+/// * `auction_proof`: proof created by `prove_auction`
+/// * `auctions`: data of each proven auction, in the order they were proven
+/// * `private_encryption_key`: owner's private key of the encryption key stored in every auction
+///
+/// returns: Result<LocallyVerifiedProof, Error> Proof with the public values of each auction
+pub fn verify_local(
+    auction_proof: AuctionProof,
+    auctions: &[AuctionData],
+    private_encryption_key: &SecretKey,
+) -> Result<LocallyVerifiedProof> {
+    ProverClient::new().verify(&auction_proof.proof, &auction_proof.vk)?;
+
+    let batch = AuctionPublicValues::decode_batch(auction_proof.proof.public_values.as_slice())?;
+    let encryption_key = PublicKey::from_secret_key(private_encryption_key);
+    if batch.iter().any(|public_values| {
+        public_values.encryption_key_hash != calc_encryption_key_hash(&encryption_key)
+    }) {
+        return Err(anyhow!(
+            "Proof is not bound to the encryption key of the auction"
+        ));
+    }
+
+    // Native run of the guest logic, the proof must commit the same outcomes
+    for auction_data in auctions {
+        auction_data.validate()?;
+    }
+    let expected_batch: Vec<AuctionPublicValues> = auctions
+        .iter()
+        .map(|auction_data| {
+            find_winner(auction_data, private_encryption_key)
+                .public_values(auction_data, &encryption_key)
+        })
+        .collect();
+    if batch != expected_batch {
+        return Err(anyhow!(
+            "Proof does not match the local outcome of the auction"
+        ));
+    }
+    for public_values in &batch {
+        if !public_values.rejected_bids.is_empty() {
+            println!(
+                "Rejected invalid bids at indices: {:?}",
+                public_values.rejected_bids
+            );
+        }
+    }
+
+    Ok(LocallyVerifiedProof {
+        proof: auction_proof.proof,
+        batch,
+    })
+}
+
+/// Submit a locally verified proof to the Aligned batcher, without waiting for the on-chain
+/// verification of the batch
+///
+/// # Arguments
+///
+/// * `wallet`: wallet of the owner, paying the fee and allowed to finalize the auctions
+/// * `verified_proof`: proof checked by `verify_local`
+/// * `rpc_url`: rpc url of the network
+/// * `network`: network supported by Aligned
+/// * `batcher_url`: Aligned batcher URL
+/// * `max_fee`: maximum fee paid to Aligned, in wei
+///
+/// returns: Result<SubmittedProof, Error> Inclusion of the proof in its batch
+pub async fn submit_to_aligned(
+    wallet: Wallet<SigningKey>,
+    verified_proof: LocallyVerifiedProof,
+    rpc_url: &str,
+    network: Network,
+    batcher_url: &str,
+    max_fee: U256,
+) -> Result<SubmittedProof> {
+    let pub_input = verified_proof.proof.public_values.to_vec();
+    let verification_data = VerificationData {
+        proving_system: ProvingSystemId::SP1,
+        proof: bincode::serialize(&verified_proof.proof)?,
+        proof_generator_addr: wallet.address(),
+        vm_program_code: Some(get_elf()?),
+        verification_key: None,
+        pub_input: Some(pub_input.clone()),
+    };
+
+    let nonce = get_next_nonce(rpc_url, wallet.address(), network)
+        .await
+        .map_err(|e| anyhow!("Failed to get next nonce: {:?}", e))?;
+
+    println!("Submitting your proof...");
+
+    let aligned_verification_data = submit(
+        batcher_url,
+        network,
+        &verification_data,
+        max_fee,
+        wallet,
+        nonce,
+    )
+    .await
+    .map_err(|e| anyhow!("Failed to submit proof: {:?}", e))?;
+
+    Ok(SubmittedProof {
+        batch: verified_proof.batch,
+        pub_input,
+        aligned_verification_data,
+    })
+}
+
+/// Wait until Aligned verifies the batch of a submitted proof on-chain
+///
+/// # Arguments
+///
+/// * `submitted_proof`: proof submitted by `submit_to_aligned`
+/// * `rpc_url`: rpc url of the network
+/// * `network`: network supported by Aligned
+///
+/// returns: Result<Option<BatchedProof>, Error> Proof verified on-chain, `None` if the batch is
+/// still not verified after `BATCH_POLL_ATTEMPTS` checks
+pub async fn wait_for_batch(
+    submitted_proof: SubmittedProof,
+    rpc_url: &str,
+    network: Network,
+) -> Result<Option<BatchedProof>> {
+    for _ in 0..BATCH_POLL_ATTEMPTS {
+        let verified =
+            is_proof_verified(&submitted_proof.aligned_verification_data, network, rpc_url)
+                .await
+                .map_err(|e| anyhow!("Failed to check the verification of the batch: {:?}", e))?;
+        if verified {
+            println!(
+                "Proof submitted and verified successfully on batch {}",
+                hex::encode(submitted_proof.aligned_verification_data.batch_merkle_root)
+            );
+            return Ok(Some(BatchedProof {
+                batch: submitted_proof.batch,
+                pub_input: submitted_proof.pub_input,
+                aligned_verification_data: submitted_proof.aligned_verification_data,
+            }));
+        }
+        tokio::time::sleep(BATCH_POLL_INTERVAL).await;
+    }
+    println!(
+        "Batch {} is not verified yet",
+        hex::encode(submitted_proof.aligned_verification_data.batch_merkle_root)
+    );
+    Ok(None)
+}
+
+/// Encode a proof verified by Aligned for the function `finalizeAuction` of the contract, which
+/// checks its inclusion in the batch
+///
+/// # Arguments
+///
+/// * `batched_proof`: proof verified by `wait_for_batch`
+///
+/// returns: Vec<u8, Global> ABI-encoded verified proof
+pub fn encode_verified_proof(batched_proof: &BatchedProof) -> Vec<u8> {
+    let aligned_verification_data = &batched_proof.aligned_verification_data;
+    let commitment = &aligned_verification_data.verification_data_commitment;
+
+    let mut index_in_batch = [0; 32];
+    U256::from(aligned_verification_data.index_in_batch).to_big_endian(&mut index_in_batch);
+
+    let merkle_path: Vec<u8> = flatten(
+        aligned_verification_data
+            .batch_inclusion_proof
+            .merkle_path
+            .as_slice(),
+    );
+
+    encode(&[
+        Token::Bytes(batched_proof.pub_input.clone()),
+        Token::FixedBytes(commitment.proof_commitment.to_vec()),
+        Token::FixedBytes(commitment.pub_input_commitment.to_vec()),
+        Token::FixedBytes(commitment.proving_system_aux_data_commitment.to_vec()),
+        Token::FixedBytes(commitment.proof_generator_addr.to_vec()),
+        Token::FixedBytes(aligned_verification_data.batch_merkle_root.to_vec()),
+        Token::Bytes(merkle_path),
+        Token::Uint(Uint::from(index_in_batch)),
+    ])
+}
+
+/// Prove the decryption of the bids of every auction by chunks of `chunk_size` bids with the
+/// chunk program, then write the chunk proofs to the input of the main program, which aggregates
+/// them
+///
+/// # Arguments
+///
+/// * `client`: SP1 prover client
+/// * `stdin`: input of the main program, after the auctions
+/// * `auctions`: data of each auction
+/// * `private_encryption_key`: owner's private key
+/// * `chunk_size`: number of bids decrypted by each chunk proof
+///
+/// returns: Result<(), Error>
+fn prove_chunks(
+    client: &ProverClient,
+    stdin: &mut SP1Stdin,
+    auctions: &[AuctionData],
+    private_encryption_key: &SecretKey,
+    chunk_size: usize,
+) -> Result<()> {
+    if chunk_size == 0 {
+        return Err(anyhow!("Chunk size must be greater than zero"));
+    }
+    let (pk, vk) = client.setup(get_chunk_elf()?.as_slice());
+
+    let mut chunks = vec![];
+    let mut proofs = vec![];
+    for auction_data in auctions {
+        let chunk_count = auction_data.bidders.len().div_ceil(chunk_size);
+        let mut auction_chunks = vec![];
+        for (index, bidders) in auction_data.bidders.chunks(chunk_size).enumerate() {
+            println!("Creating proof of chunk {}/{}...", index + 1, chunk_count);
+            let mut chunk_stdin = SP1Stdin::new();
+            chunk_stdin.write(&bidders);
+            chunk_stdin.write(&auction_data.id);
+            chunk_stdin.write(&auction_data.cipher_scheme);
+            chunk_stdin.write(&private_encryption_key.serialize().to_vec());
+
+            let proof = client.prove(&pk, chunk_stdin).compressed().run()?;
+            client.verify(&proof, &vk)?;
+            auction_chunks.push(proof.public_values.to_vec());
+            let SP1Proof::Compressed(proof) = proof.proof else {
+                return Err(anyhow!("Chunk proof is not compressed"));
+            };
+            proofs.push(proof);
+        }
+        chunks.push(auction_chunks);
+    }
+
+    stdin.write(&ProgramInput::Chunks {
+        encryption_key: PublicKey::from_secret_key(private_encryption_key)
+            .serialize()
+            .to_vec(),
+        chunks,
+    });
+    for proof in proofs {
+        stdin.write_proof(proof, vk.vk.clone());
+    }
+    Ok(())
+}
@@ -0,0 +1,29 @@
+use std::path::PathBuf;
+
+/// Path of the digest of the verification key of the chunk program, written by `make chunk-elf`
+const CHUNK_VK_DIGEST: &str = "elf/chunk_vk_digest";
+
+/// Expose the digest to the main program. The zkVM build fails with a clear message until the
+/// digest exists, while a host build falls back to a zero digest so the crate still builds, lints
+/// and tests on a clean checkout. The host never verifies chunk proofs with it.
+pub fn main() {
+    println!("cargo:rerun-if-changed={}", CHUNK_VK_DIGEST);
+    let path = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap()).join(CHUNK_VK_DIGEST);
+    if path.exists() {
+        println!("cargo:rustc-env=CHUNK_VK_DIGEST_PATH={}", path.display());
+        return;
+    }
+    if std::env::var("CARGO_CFG_TARGET_OS").as_deref() == Ok("zkvm") {
+        panic!("{} is missing, run `make chunk-elf` first", CHUNK_VK_DIGEST);
+    }
+    println!(
+        "cargo:warning={} is missing, using a zero digest, run `make chunk-elf` before building the ELF",
+        CHUNK_VK_DIGEST
+    );
+    let placeholder = PathBuf::from(std::env::var("OUT_DIR").unwrap()).join("chunk_vk_digest");
+    std::fs::write(&placeholder, "[0, 0, 0, 0, 0, 0, 0, 0]").unwrap();
+    println!(
+        "cargo:rustc-env=CHUNK_VK_DIGEST_PATH={}",
+        placeholder.display()
+    );
+}
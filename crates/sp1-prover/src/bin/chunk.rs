@@ -0,0 +1,20 @@
+#![no_main]
+
+use aligned_sp1_prover::{BidChunk, Bidder, CipherScheme};
+use ecies::SecretKey;
+
+sp1_zkvm::entrypoint!(main);
+
+/// Entrypoint for the chunk program, decrypting a chunk of the bids of an auction. Its proofs are
+/// aggregated by the main program.
+pub fn main() {
+    let bidders = sp1_zkvm::io::read::<Vec<Bidder>>();
+    let auction_id = sp1_zkvm::io::read::<Vec<u8>>();
+    let cipher_scheme = sp1_zkvm::io::read::<CipherScheme>();
+
+    let pvk = SecretKey::parse_slice(&sp1_zkvm::io::read::<Vec<u8>>())
+        .expect("missing private key to encode bidder data");
+
+    let chunk = BidChunk::decrypt(&bidders, &auction_id, cipher_scheme, &pvk);
+    sp1_zkvm::io::commit_slice(&chunk.encode());
+}
@@ -0,0 +1,285 @@
+use std::fmt::{Display, Formatter};
+
+use alloy_sol_types::{sol, SolValue};
+use ecies::{PublicKey, SecretKey};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bid_leaf, calc_encryption_key_hash, decrypt_bidder_data, keccak256, AuctionData, BidError,
+    Bidder, CipherScheme,
+};
+
+/// Number of bids decrypted by one proof of the chunk program
+pub const DEFAULT_CHUNK_SIZE: usize = 8;
+
+/// Input of the main zkVM program read after the auctions, deciding how the bids are decrypted
+#[derive(Deserialize, Serialize, Debug)]
+pub enum ProgramInput {
+    /// Decrypt every bid with the owner's serialized private key
+    PrivateKey(Vec<u8>),
+    /// Aggregate the proofs of the chunk program, which decrypted the bids chunk by chunk. The
+    /// proofs are written with `SP1Stdin::write_proof` in the order of `chunks`.
+    Chunks {
+        /// Owner's serialized public key
+        encryption_key: Vec<u8>,
+        /// Public values of the chunk proofs of each auction, in bid order, see `BidChunk`
+        chunks: Vec<Vec<Vec<u8>>>,
+    },
+}
+
+sol! {
+    /// Public values of the chunk program: the bids of a chunk decrypted with the owner's key
+    #[derive(Debug, PartialEq, Eq)]
+    struct BidChunk {
+        /// Hash of the owner's public encryption key, see `calc_encryption_key_hash`
+        bytes32 encryption_key_hash;
+        /// Id of the auction the bids are bound to
+        bytes auction_id;
+        /// Scheme the bids were encrypted with, see `CipherScheme`
+        uint8 cipher_scheme;
+        /// Hash of the leaves of the decrypted bids, see `bid_leaf`
+        bytes32 bids_digest;
+        /// Decrypted amount of each bid, 0 if the bid is invalid
+        uint128[] amounts;
+        /// Whether each bid is valid, see `decrypt_bidder_data`
+        bool[] valid;
+    }
+}
+
+/// Error returned when the chunk proofs do not match an auction
+#[derive(Debug)]
+pub enum ChunkError {
+    /// The data is not a valid ABI encoding of `BidChunk`
+    Decode(alloy_sol_types::Error),
+    /// A chunk was decrypted with another key than the owner's key
+    EncryptionKey,
+    /// A chunk was decrypted for another auction or with another scheme
+    Auction,
+    /// The chunks do not cover the bids of the auction in bid order
+    Bids,
+}
+
+impl Display for ChunkError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChunkError::Decode(e) => write!(f, "failed to decode chunk: {}", e),
+            ChunkError::EncryptionKey => write!(f, "chunk is bound to another encryption key"),
+            ChunkError::Auction => write!(f, "chunk is bound to another auction"),
+            ChunkError::Bids => write!(f, "chunks do not match the bids of the auction"),
+        }
+    }
+}
+
+impl std::error::Error for ChunkError {}
+
+impl BidChunk {
+    /// Decrypt a chunk of the bids of an auction, run by the chunk program
+    ///
+    /// # Arguments
+    ///
+    /// * `bidders`: encrypted bidder data of the chunk, in bid order
+    /// * `auction_id`: id of the auction in bytes
+    /// * `cipher_scheme`: scheme of the auction
+    /// * `pvk`: owner's private key
+    ///
+    /// returns: BidChunk Decrypted bids
+    pub fn decrypt(
+        bidders: &[Bidder],
+        auction_id: &[u8],
+        cipher_scheme: CipherScheme,
+        pvk: &SecretKey,
+    ) -> Self {
+        let bidder_amounts: Vec<Result<u128, BidError>> = bidders
+            .iter()
+            .map(|bidder| decrypt_bidder_data(cipher_scheme.cipher(), pvk, bidder, auction_id))
+            .collect();
+        BidChunk {
+            encryption_key_hash: calc_encryption_key_hash(&PublicKey::from_secret_key(pvk)).into(),
+            auction_id: auction_id.to_vec().into(),
+            cipher_scheme: cipher_scheme as u8,
+            bids_digest: bids_digest(bidders).into(),
+            amounts: bidder_amounts
+                .iter()
+                .map(|amount| amount.unwrap_or(0))
+                .collect(),
+            valid: bidder_amounts.iter().map(Result::is_ok).collect(),
+        }
+    }
+
+    /// Encode the chunk to the bytes committed by the chunk program
+    ///
+    /// returns: Vec<u8, Global> ABI-encoded chunk
+    pub fn encode(&self) -> Vec<u8> {
+        self.abi_encode()
+    }
+
+    /// Decode the public values of a chunk proof
+    ///
+    /// # Arguments
+    ///
+    /// * `data`: ABI-encoded chunk
+    ///
+    /// returns: Result<BidChunk, ChunkError> Decoded chunk
+    pub fn decode(data: &[u8]) -> Result<Self, ChunkError> {
+        <Self as SolValue>::abi_decode(data, true).map_err(ChunkError::Decode)
+    }
+}
+
+/// Join the chunks decrypting the bids of an auction, checking they are bound to the auction and
+/// to the owner's key. Invalid bids are all reported as `BidError::Undecryptable`, they are
+/// rejected the same way whatever the reason.
+///
+/// # Arguments
+///
+/// * `auction_data`: data of the auction
+/// * `chunks`: chunks of the auction, in bid order
+/// * `pbk`: owner's public key
+///
+/// returns: Result<Vec<Result<u128, BidError>, Global>, ChunkError> Decrypted amount of every bid
+/// in bid order, see `rank_bids`
+pub fn aggregate_chunks(
+    auction_data: &AuctionData,
+    chunks: &[BidChunk],
+    pbk: &PublicKey,
+) -> Result<Vec<Result<u128, BidError>>, ChunkError> {
+    let encryption_key_hash = calc_encryption_key_hash(pbk);
+    let mut bidder_amounts = vec![];
+    for chunk in chunks {
+        if chunk.encryption_key_hash != encryption_key_hash {
+            return Err(ChunkError::EncryptionKey);
+        }
+        if chunk.auction_id != auction_data.id
+            || chunk.cipher_scheme != auction_data.cipher_scheme as u8
+        {
+            return Err(ChunkError::Auction);
+        }
+        let start = bidder_amounts.len();
+        let end = start + chunk.amounts.len();
+        if chunk.amounts.is_empty()
+            || chunk.valid.len() != chunk.amounts.len()
+            || end > auction_data.bidders.len()
+            || chunk.bids_digest != bids_digest(&auction_data.bidders[start..end])
+        {
+            return Err(ChunkError::Bids);
+        }
+        for (amount, valid) in chunk.amounts.iter().zip(&chunk.valid) {
+            bidder_amounts.push(if *valid {
+                Ok(*amount)
+            } else {
+                Err(BidError::Undecryptable)
+            });
+        }
+    }
+    if bidder_amounts.len() != auction_data.bidders.len() {
+        return Err(ChunkError::Bids);
+    }
+    Ok(bidder_amounts)
+}
+
+fn bids_digest(bidders: &[Bidder]) -> [u8; 32] {
+    keccak256(&bidders.iter().flat_map(bid_leaf).collect::<Vec<u8>>())
+}
+
+#[cfg(test)]
+mod tests {
+    use ecies::{PublicKey, SecretKey};
+    use rand::rngs::OsRng;
+
+    use crate::chunk::{aggregate_chunks, BidChunk, ChunkError};
+    use crate::{
+        encode_bid, find_winner, rank_bids, AuctionData, Bidder, CipherScheme, PricingRule,
+    };
+
+    fn auction_data(pbk: &PublicKey, amounts: &[u128]) -> AuctionData {
+        AuctionData {
+            bidders: amounts
+                .iter()
+                .enumerate()
+                .map(|(i, amount)| Bidder {
+                    encrypted_amount: CipherScheme::Ecies
+                        .cipher()
+                        .encrypt(
+                            &pbk.serialize(),
+                            &encode_bid(*amount, &[i as u8; 20], &[0; 32]),
+                        )
+                        .unwrap(),
+                    address: vec![i as u8; 20],
+                })
+                .collect(),
+            pricing_rule: PricingRule::SecondPrice,
+            units: 2,
+            reveal_bids: true,
+            ..AuctionData::test_default()
+        }
+    }
+
+    fn chunks(auction_data: &AuctionData, pvk: &SecretKey, chunk_size: usize) -> Vec<BidChunk> {
+        auction_data
+            .bidders
+            .chunks(chunk_size)
+            .map(|bidders| {
+                let chunk =
+                    BidChunk::decrypt(bidders, &auction_data.id, auction_data.cipher_scheme, pvk);
+                BidChunk::decode(&chunk.encode()).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_aggregate_chunks() {
+        let pvk = SecretKey::random(&mut OsRng);
+        let pbk = PublicKey::from_secret_key(&pvk);
+        let mut data = auction_data(&pbk, &[5, 9, 0, 2000, 7]);
+        data.bidders[1].address = vec![9; 20];
+
+        // The outcome does not depend on the chunk size
+        for chunk_size in 1..=5 {
+            let bidder_amounts = aggregate_chunks(&data, &chunks(&data, &pvk, chunk_size), &pbk);
+            assert_eq!(
+                rank_bids(&data, &bidder_amounts.unwrap()),
+                find_winner(&data, &pvk)
+            );
+        }
+    }
+
+    #[test]
+    fn test_aggregate_invalid_chunks() {
+        let pvk = SecretKey::random(&mut OsRng);
+        let pbk = PublicKey::from_secret_key(&pvk);
+        let data = auction_data(&pbk, &[5, 9, 7]);
+        let chunks = chunks(&data, &pvk, 2);
+
+        // A chunk is missing
+        assert!(matches!(
+            aggregate_chunks(&data, &chunks[..1], &pbk),
+            Err(ChunkError::Bids)
+        ));
+        // The chunks are out of order
+        let swapped = [chunks[1].clone(), chunks[0].clone()];
+        assert!(matches!(
+            aggregate_chunks(&data, &swapped, &pbk),
+            Err(ChunkError::Bids)
+        ));
+        // The chunks were decrypted with another key
+        let other_pbk = PublicKey::from_secret_key(&SecretKey::random(&mut OsRng));
+        assert!(matches!(
+            aggregate_chunks(&data, &chunks, &other_pbk),
+            Err(ChunkError::EncryptionKey)
+        ));
+        // The chunks were decrypted for another auction
+        let mut other_auction = auction_data(&pbk, &[5, 9, 7]);
+        other_auction.id = vec![1; 32];
+        assert!(matches!(
+            aggregate_chunks(&other_auction, &chunks, &pbk),
+            Err(ChunkError::Auction)
+        ));
+        // A bid is added to a chunk
+        let mut forged = chunks.clone();
+        forged[0].amounts.push(100);
+        forged[0].valid.push(true);
+        assert!(matches!(
+            aggregate_chunks(&data, &forged, &pbk),
+            Err(ChunkError::Bids)
+        ));
+    }
+}
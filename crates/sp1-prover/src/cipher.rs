@@ -0,0 +1,113 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::BidError;
+
+/// Scheme encrypting the bids to the owner's public key, so they can only be read inside the
+/// zkVM program
+pub trait BidCipher {
+    /// Encrypt the plaintext of a bid, see `encode_bid`
+    ///
+    /// # Arguments
+    ///
+    /// * `pbk`: owner's serialized public key
+    /// * `plaintext`: plaintext of the bid
+    ///
+    /// returns: Result<Vec<u8, Global>, BidError> encrypted bid
+    fn encrypt(&self, pbk: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, BidError>;
+
+    /// Decrypt an encrypted bid
+    ///
+    /// # Arguments
+    ///
+    /// * `pvk`: owner's serialized private key
+    /// * `ciphertext`: encrypted bid
+    ///
+    /// returns: Result<Vec<u8, Global>, BidError> plaintext of the bid
+    fn decrypt(&self, pvk: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, BidError>;
+}
+
+/// ECIES on secp256k1 with AES-256-GCM
+pub struct Ecies;
+
+impl BidCipher for Ecies {
+    fn encrypt(&self, pbk: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, BidError> {
+        ecies::encrypt(pbk, plaintext).map_err(|_| BidError::Unencryptable)
+    }
+
+    fn decrypt(&self, pvk: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, BidError> {
+        ecies::decrypt(pvk, ciphertext).map_err(|_| BidError::Undecryptable)
+    }
+}
+
+/// Id of the scheme encrypting the bids, chosen at auction creation
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CipherScheme {
+    /// See `Ecies`
+    #[default]
+    Ecies,
+}
+
+impl CipherScheme {
+    /// Implementation of the scheme
+    pub fn cipher(&self) -> &'static dyn BidCipher {
+        match self {
+            CipherScheme::Ecies => &Ecies,
+        }
+    }
+}
+
+impl TryFrom<u8> for CipherScheme {
+    type Error = String;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(CipherScheme::Ecies),
+            _ => Err(format!("unknown cipher scheme: {}", value)),
+        }
+    }
+}
+
+impl FromStr for CipherScheme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ecies" => Ok(CipherScheme::Ecies),
+            _ => Err(format!("unknown cipher scheme: {}", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ecies::{PublicKey, SecretKey};
+    use rand::rngs::OsRng;
+
+    use crate::cipher::CipherScheme;
+    use crate::BidError;
+
+    #[test]
+    fn test_round_trip() {
+        let pvk = SecretKey::random(&mut OsRng);
+        let pbk = PublicKey::from_secret_key(&pvk);
+        let cipher = CipherScheme::Ecies.cipher();
+        let ciphertext = cipher.encrypt(&pbk.serialize(), &[1, 2, 3]).unwrap();
+        assert_eq!(
+            cipher.decrypt(&pvk.serialize(), &ciphertext),
+            Ok(vec![1, 2, 3])
+        );
+        assert_eq!(
+            cipher.decrypt(&pvk.serialize(), &[1, 2, 3]),
+            Err(BidError::Undecryptable)
+        );
+    }
+
+    #[test]
+    fn test_scheme_id() {
+        assert_eq!(CipherScheme::try_from(0), Ok(CipherScheme::Ecies));
+        assert!(CipherScheme::try_from(1).is_err());
+        assert_eq!("ecies".parse(), Ok(CipherScheme::Ecies));
+    }
+}
@@ -1,37 +1,426 @@
-use ecies::SecretKey;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use ecies::{PublicKey, SecretKey};
 use serde::{Deserialize, Serialize};
 use tiny_keccak::{Hasher, Keccak};
 
+pub use crate::chunk::{aggregate_chunks, BidChunk, ChunkError, ProgramInput, DEFAULT_CHUNK_SIZE};
+pub use crate::cipher::{BidCipher, CipherScheme, Ecies};
+pub use crate::merkle::{bid_leaf, bids_root, inclusion_proof, verify_inclusion};
+pub use crate::outcome::{find_winner, rank_bids, AuctionOutcome};
+pub use crate::public_values::{
+    AuctionPublicValues, Payout, PublicValuesError, RevealedBid, PUBLIC_VALUES_VERSION,
+};
+
+mod chunk;
+mod cipher;
+mod merkle;
+mod outcome;
+mod public_values;
+
+/// Version of the auction data layout hashed by `calc_auction_hash`
+pub const AUCTION_DATA_VERSION: u8 = 11;
+
+/// Denominator of the fee and royalty basis points
+const BPS_DENOMINATOR: u64 = 10_000;
+
 #[derive(Deserialize, Serialize, Debug)]
 pub struct AuctionData {
     pub bidders: Vec<Bidder>,
     pub id: Vec<u8>,
+    pub pricing_rule: PricingRule,
+    pub reserve_price: u128,
+    pub tie_break: TieBreak,
+    /// Number of identical items sold, each winner gets one item
+    pub units: u32,
+    /// Layout version, equal to `AUCTION_DATA_VERSION`
+    pub version: u8,
+    /// Id of the chain where the auction contract is deployed
+    pub chain_id: u64,
+    /// Address of the auction contract
+    pub contract_address: Vec<u8>,
+    /// Rule deciding which bids win
+    pub auction_rule: AuctionRule,
+    /// Ordering of the bids, highest first for a sale or lowest first for a procurement
+    pub direction: AuctionDirection,
+    /// Deposit of every bidder, a sale can not charge more so higher bids are rejected
+    pub deposit_price: u128,
+    /// Whether every bid is revealed in the public values once the auction ends
+    pub reveal_bids: bool,
+    /// Scheme used by the bidders to encrypt their bids
+    pub cipher_scheme: CipherScheme,
+    /// Protocol fee taken from the proceeds of a sale, in basis points
+    pub fee_bps: u16,
+    /// Address receiving the protocol fee
+    pub fee_recipient: Vec<u8>,
+    /// Royalty taken from the proceeds of a sale, in basis points
+    pub royalty_bps: u16,
+    /// Address receiving the royalty
+    pub royalty_recipient: Vec<u8>,
+}
+
+impl AuctionData {
+    /// Sale of one unit without bids, fee or royalty, for tests to override with `..`
+    ///
+    /// returns: AuctionData
+    pub fn test_default() -> Self {
+        AuctionData {
+            bidders: vec![],
+            id: vec![0; 32],
+            pricing_rule: PricingRule::FirstPrice,
+            reserve_price: 0,
+            tie_break: TieBreak::EarliestBid,
+            units: 1,
+            version: AUCTION_DATA_VERSION,
+            chain_id: 17000,
+            contract_address: vec![0; 20],
+            auction_rule: AuctionRule::HighestBid,
+            direction: AuctionDirection::Forward,
+            deposit_price: 1000,
+            reveal_bids: false,
+            cipher_scheme: CipherScheme::Ecies,
+            fee_bps: 0,
+            fee_recipient: vec![0; 20],
+            royalty_bps: 0,
+            royalty_recipient: vec![0; 20],
+        }
+    }
+
+    /// Check the auction can be settled, the contract rejects the same auctions at creation
+    ///
+    /// returns: Result<(), AuctionDataError> Error if the payout of a sale can not be computed
+    pub fn validate(&self) -> Result<(), AuctionDataError> {
+        if u64::from(self.fee_bps) + u64::from(self.royalty_bps) > BPS_DENOMINATOR {
+            return Err(AuctionDataError::PayoutAboveProceeds);
+        }
+        Ok(())
+    }
+}
+
+/// Reason why an auction can not be proven
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuctionDataError {
+    /// The fee and the royalty take more than the proceeds of the sale
+    PayoutAboveProceeds,
+}
+
+impl Display for AuctionDataError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuctionDataError::PayoutAboveProceeds => {
+                write!(f, "fee and royalty exceed the proceeds")
+            }
+        }
+    }
 }
 
+impl std::error::Error for AuctionDataError {}
+
 #[derive(Deserialize, Serialize, Debug)]
 pub struct Bidder {
     pub encrypted_amount: Vec<u8>,
     pub address: Vec<u8>,
 }
 
+/// Rule deciding which bids win the auction
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AuctionRule {
+    /// The highest bids win
+    #[default]
+    HighestBid,
+    /// The lowest bids submitted by exactly one bidder win (Lowest Unique Bid Auction)
+    LowestUniqueBid,
+}
+
+impl TryFrom<u8> for AuctionRule {
+    type Error = String;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(AuctionRule::HighestBid),
+            1 => Ok(AuctionRule::LowestUniqueBid),
+            _ => Err(format!("unknown auction rule: {}", value)),
+        }
+    }
+}
+
+impl FromStr for AuctionRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "highest-bid" => Ok(AuctionRule::HighestBid),
+            "lowest-unique-bid" => Ok(AuctionRule::LowestUniqueBid),
+            _ => Err(format!("unknown auction rule: {}", s)),
+        }
+    }
+}
+
+/// Direction of the auction, deciding which amounts rank first
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AuctionDirection {
+    /// Sale: the highest bids win, the reserve price is the minimum price accepted by the owner
+    #[default]
+    Forward,
+    /// Procurement: the lowest bids win, the reserve price is the maximum price accepted by the
+    /// owner
+    Reverse,
+}
+
+impl AuctionDirection {
+    /// Whether the owner accepts a bid amount
+    ///
+    /// # Arguments
+    ///
+    /// * `amount`: amount of the bid
+    /// * `reserve_price`: minimum price of a sale or maximum price of a procurement
+    ///
+    /// returns: bool `true` if the bid can win
+    pub fn accepts(&self, amount: u128, reserve_price: u128) -> bool {
+        match self {
+            AuctionDirection::Forward => amount >= reserve_price,
+            AuctionDirection::Reverse => amount <= reserve_price,
+        }
+    }
+}
+
+impl TryFrom<u8> for AuctionDirection {
+    type Error = String;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(AuctionDirection::Forward),
+            1 => Ok(AuctionDirection::Reverse),
+            _ => Err(format!("unknown auction direction: {}", value)),
+        }
+    }
+}
+
+impl FromStr for AuctionDirection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "forward" => Ok(AuctionDirection::Forward),
+            "reverse" => Ok(AuctionDirection::Reverse),
+            _ => Err(format!("unknown auction direction: {}", s)),
+        }
+    }
+}
+
+/// Rule deciding the uniform price every winner pays, or is paid in a procurement
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PricingRule {
+    /// The winners pay the last winning bid, i.e. their own bid when a single item is sold
+    #[default]
+    FirstPrice,
+    /// The winners pay the first losing bid, i.e. the second-best bid when a single item is sold
+    /// (Vickrey auction)
+    SecondPrice,
+}
+
+impl PricingRule {
+    /// Calculate the clearing price of the auction
+    ///
+    /// # Arguments
+    ///
+    /// * `direction`: direction of the auction
+    /// * `last_winning_amount`: amount of the worst ranked winning bid
+    /// * `first_losing_amount`: amount of the best ranked losing bid, if any
+    /// * `reserve_price`: minimum price of a sale or maximum price of a procurement
+    ///
+    /// returns: u128 Price paid by or to each winner
+    pub fn clearing_price(
+        &self,
+        direction: AuctionDirection,
+        last_winning_amount: u128,
+        first_losing_amount: Option<u128>,
+        reserve_price: u128,
+    ) -> u128 {
+        match (self, direction) {
+            (PricingRule::FirstPrice, _) => last_winning_amount,
+            (PricingRule::SecondPrice, AuctionDirection::Forward) => {
+                first_losing_amount.unwrap_or(0).max(reserve_price)
+            }
+            (PricingRule::SecondPrice, AuctionDirection::Reverse) => first_losing_amount
+                .unwrap_or(reserve_price)
+                .min(reserve_price),
+        }
+    }
+}
+
+impl TryFrom<u8> for PricingRule {
+    type Error = String;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(PricingRule::FirstPrice),
+            1 => Ok(PricingRule::SecondPrice),
+            _ => Err(format!("unknown pricing rule: {}", value)),
+        }
+    }
+}
+
+impl FromStr for PricingRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "first-price" => Ok(PricingRule::FirstPrice),
+            "second-price" => Ok(PricingRule::SecondPrice),
+            _ => Err(format!("unknown pricing rule: {}", s)),
+        }
+    }
+}
+
+/// Policy ranking the bids tied at the same amount
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TieBreak {
+    /// The earliest bid wins
+    #[default]
+    EarliestBid,
+    /// A pseudo-random bid wins, seeded by the auction hash
+    Random,
+}
+
+impl TieBreak {
+    /// Pick the winner among the tied bids
+    ///
+    /// # Arguments
+    ///
+    /// * `tied`: number of bids tied at the same amount, in bid order
+    /// * `auction_hash`: hash of the auction data, see `calc_auction_hash`
+    ///
+    /// returns: usize Index of the winner among the tied bids
+    pub fn pick(&self, tied: usize, auction_hash: &[u8; 32]) -> usize {
+        match self {
+            TieBreak::EarliestBid => 0,
+            TieBreak::Random => {
+                let seed = u64::from_be_bytes(auction_hash[24..].try_into().unwrap());
+                (seed % tied as u64) as usize
+            }
+        }
+    }
+
+    /// Rank the tied bids by picking the winner among the remaining bids until none is left
+    ///
+    /// # Arguments
+    ///
+    /// * `tied`: number of bids tied at the same amount, in bid order
+    /// * `auction_hash`: hash of the auction data, see `calc_auction_hash`
+    ///
+    /// returns: Vec<usize, Global> Indices of the tied bids, from the best ranked
+    pub fn rank(&self, tied: usize, auction_hash: &[u8; 32]) -> Vec<usize> {
+        let mut remaining: Vec<usize> = (0..tied).collect();
+        let mut ranking = Vec::with_capacity(tied);
+        while !remaining.is_empty() {
+            ranking.push(remaining.remove(self.pick(remaining.len(), auction_hash)));
+        }
+        ranking
+    }
+}
+
+impl TryFrom<u8> for TieBreak {
+    type Error = String;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(TieBreak::EarliestBid),
+            1 => Ok(TieBreak::Random),
+            _ => Err(format!("unknown tie-break policy: {}", value)),
+        }
+    }
+}
+
+impl FromStr for TieBreak {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "earliest" => Ok(TieBreak::EarliestBid),
+            "random" => Ok(TieBreak::Random),
+            _ => Err(format!("unknown tie-break policy: {}", s)),
+        }
+    }
+}
+
+/// Reason why a bid is excluded from the auction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BidError {
+    /// The bid can not be encrypted with the owner's public key
+    Unencryptable,
+    /// The encrypted amount can not be decrypted with the owner's private key
+    Undecryptable,
+    /// The decrypted data is not a valid amount
+    MalformedAmount,
+    /// The bid was encrypted for another bidder or another auction, e.g. a copied ciphertext
+    ForeignBid,
+}
+
+impl Display for BidError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BidError::Unencryptable => write!(f, "failed to encrypt bid"),
+            BidError::Undecryptable => write!(f, "failed to decrypt bid"),
+            BidError::MalformedAmount => write!(f, "malformed bid amount"),
+            BidError::ForeignBid => write!(f, "bid is bound to another bidder or auction"),
+        }
+    }
+}
+
+impl std::error::Error for BidError {}
+
+/// Encode the plaintext of a bid: the amount followed by the bidder address and the auction id,
+/// so a ciphertext copied by another bidder or into another auction is rejected
+///
+/// # Arguments
+///
+/// * `amount`: bid amount
+/// * `bidder_address`: address of the bidder
+/// * `auction_id`: id of the auction in bytes
+///
+/// returns: Vec<u8, Global> plaintext of the bid
+pub fn encode_bid(amount: u128, bidder_address: &[u8], auction_id: &[u8]) -> Vec<u8> {
+    let mut plaintext = amount.to_be_bytes().to_vec();
+    plaintext.extend(bidder_address);
+    plaintext.extend(auction_id);
+    plaintext
+}
+
 /// Decrypt the encrypted bidder data using owner's private key
 ///
 /// # Arguments
 ///
+/// * `cipher`: scheme the bid was encrypted with, see `CipherScheme::cipher`
 /// * `pvk`: owner's private key
 /// * `bidder`: encrypted bidder data
+/// * `auction_id`: id of the auction in bytes
 ///
-/// returns: u128 Bidder amount
-pub fn decrypt_bidder_data(pvk: &SecretKey, bidder: &Bidder) -> u128 {
-    u128::from_be_bytes(
-        ecies::decrypt(&pvk.serialize(), &bidder.encrypted_amount)
-            .expect("failed to decrypt")
-            .try_into()
-            .unwrap(),
-    )
+/// returns: Result<u128, BidError> Bidder amount, or the reason the bid is invalid
+pub fn decrypt_bidder_data(
+    cipher: &dyn BidCipher,
+    pvk: &SecretKey,
+    bidder: &Bidder,
+    auction_id: &[u8],
+) -> Result<u128, BidError> {
+    let data = cipher.decrypt(&pvk.serialize(), &bidder.encrypted_amount)?;
+    if data.len() < 16 {
+        return Err(BidError::MalformedAmount);
+    }
+    let (amount, binding) = data.split_at(16);
+    if binding != [bidder.address.as_slice(), auction_id].concat() {
+        return Err(BidError::ForeignBid);
+    }
+    Ok(u128::from_be_bytes(amount.try_into().unwrap()))
 }
 
-/// Calculate the hash of the auction data to ensure the integrity of the data
+/// Calculate the hash of the auction data to ensure the integrity of the data.
+/// The hash is domain-separated by the chain id and the contract address, so a proof can not be
+/// replayed against an auction with the same id on another deployment. The bids are committed
+/// through their Merkle root, so a bidder can prove the inclusion of their bid, see `bids_root`.
+/// The id and the addresses are packed without length, so their size is fixed to read the
+/// preimage one way only.
 ///
 /// # Arguments
 ///
@@ -39,53 +428,128 @@ pub fn decrypt_bidder_data(pvk: &SecretKey, bidder: &Bidder) -> u128 {
 ///
 /// returns: [u8; 32] hash(data) in bytes
 pub fn calc_auction_hash(auction_data: &AuctionData) -> [u8; 32] {
+    assert_eq!(auction_data.id.len(), 32, "auction id must be 32 bytes");
+    for address in [
+        &auction_data.contract_address,
+        &auction_data.fee_recipient,
+        &auction_data.royalty_recipient,
+    ] {
+        assert_eq!(address.len(), 20, "address must be 20 bytes");
+    }
     let mut input = vec![];
-    let mut hasher = Keccak::v256();
 
+    input.push(auction_data.version);
+    input.extend(auction_data.chain_id.to_be_bytes());
+    input.extend(&auction_data.contract_address);
     input.extend(&auction_data.id);
-    for bidder in &auction_data.bidders {
-        input.extend(&bidder.address);
-        input.extend(&bidder.encrypted_amount);
-    }
+    input.push(auction_data.pricing_rule as u8);
+    input.extend(auction_data.reserve_price.to_be_bytes());
+    input.push(auction_data.tie_break as u8);
+    input.extend(auction_data.units.to_be_bytes());
+    input.push(auction_data.auction_rule as u8);
+    input.push(auction_data.direction as u8);
+    input.extend(auction_data.deposit_price.to_be_bytes());
+    input.push(auction_data.reveal_bids as u8);
+    input.push(auction_data.cipher_scheme as u8);
+    input.extend(auction_data.fee_bps.to_be_bytes());
+    input.extend(&auction_data.fee_recipient);
+    input.extend(auction_data.royalty_bps.to_be_bytes());
+    input.extend(&auction_data.royalty_recipient);
+    input.extend(bids_root(&auction_data.bidders));
+
+    keccak256(&input)
+}
 
+/// Calculate the hash of the owner's public encryption key, as stored in the auction contract
+///
+/// # Arguments
+///
+/// * `pbk`: owner's public key
+///
+/// returns: [u8; 32] hash(serialized public key) in bytes
+pub fn calc_encryption_key_hash(pbk: &PublicKey) -> [u8; 32] {
+    keccak256(&pbk.serialize())
+}
+
+fn keccak256(input: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak::v256();
     let mut output = [0u8; 32];
-    hasher.update(&input);
+    hasher.update(input);
     hasher.finalize(&mut output);
     output
 }
 
 #[cfg(test)]
 mod tests {
-    use std::fs;
-
     use ecies::{PublicKey, SecretKey};
     use rand::rngs::OsRng;
 
-    use crate::{calc_auction_hash, AuctionData, Bidder};
+    use crate::{
+        calc_auction_hash, encode_bid, AuctionData, AuctionDirection, AuctionRule, BidError,
+        Bidder, CipherScheme, Ecies, PricingRule, TieBreak,
+    };
 
     #[test]
     fn test_decrypt_data() {
         let (pvk, pbk) = get_key();
         // let mut rng = rand::thread_rng();
         let bidder = Bidder {
-            encrypted_amount: encrypt_bidder_amount(&(1e23 as u128), &pbk),
-            address: vec![0; 32],
+            encrypted_amount: encrypt_bidder_amount(&(1e23 as u128), &[0; 20], &[0; 32], &pbk),
+            address: vec![0; 20],
         };
-        let amount = super::decrypt_bidder_data(&pvk, &bidder);
+        let amount = super::decrypt_bidder_data(&Ecies, &pvk, &bidder, &[0; 32]).unwrap();
         assert_eq!(amount, 1e23 as u128);
     }
 
     #[test]
-    fn test_gen_key() {
-        let mut rng = OsRng;
-        let pvk = SecretKey::random(&mut rng);
+    fn test_decrypt_invalid_data() {
+        let pvk = SecretKey::random(&mut OsRng);
         let pbk = PublicKey::from_secret_key(&pvk);
-        let pvk = hex::encode(pvk.serialize());
-        let pbk = hex::encode(pbk.serialize());
-        println!("Private key: {}", &pvk);
-        println!("Public key: {}", &pbk);
-        fs::write("private_encryption_key", pvk).expect("failed to write private key to file");
-        fs::write("encryption_key", pbk).expect("failed to write public key to file");
+        let garbage = Bidder {
+            encrypted_amount: vec![1, 2, 3],
+            address: vec![0; 20],
+        };
+        assert_eq!(
+            super::decrypt_bidder_data(&Ecies, &pvk, &garbage, &[0; 32]),
+            Err(BidError::Undecryptable)
+        );
+        let wrong_length = Bidder {
+            encrypted_amount: ecies::encrypt(&pbk.serialize(), &[1; 8]).unwrap(),
+            address: vec![0; 20],
+        };
+        assert_eq!(
+            super::decrypt_bidder_data(&Ecies, &pvk, &wrong_length, &[0; 32]),
+            Err(BidError::MalformedAmount)
+        );
+    }
+
+    #[test]
+    fn test_decrypt_foreign_bid() {
+        let pvk = SecretKey::random(&mut OsRng);
+        let pbk = PublicKey::from_secret_key(&pvk);
+        let encrypted_amount = encrypt_bidder_amount(&100, &[1; 20], &[0; 32], &pbk);
+        let bidder = Bidder {
+            encrypted_amount: encrypted_amount.clone(),
+            address: vec![1; 20],
+        };
+        assert_eq!(
+            super::decrypt_bidder_data(&Ecies, &pvk, &bidder, &[0; 32]),
+            Ok(100)
+        );
+        // The ciphertext is copied by another bidder
+        let copied = Bidder {
+            encrypted_amount,
+            address: vec![2; 20],
+        };
+        assert_eq!(
+            super::decrypt_bidder_data(&Ecies, &pvk, &copied, &[0; 32]),
+            Err(BidError::ForeignBid)
+        );
+        // The ciphertext is replayed in another auction
+        assert_eq!(
+            super::decrypt_bidder_data(&Ecies, &pvk, &bidder, &[1; 32]),
+            Err(BidError::ForeignBid)
+        );
     }
 
     #[test]
@@ -123,22 +587,131 @@ mod tests {
                     ],
                 },
             ],
-            id: vec![0; 32],
+            contract_address: hex::decode("666fdd363f32e3b0efac35739aa9e2ee52a4b50e").unwrap(),
+            fee_bps: 250,
+            fee_recipient: vec![1; 20],
+            royalty_bps: 500,
+            royalty_recipient: vec![2; 20],
+            ..AuctionData::test_default()
         };
         assert_eq!(
             hex::encode(calc_auction_hash(&data)),
-            "eec15d5f07c54d6bc722494c90d0404960ec255907c62e8928e2969a186a2350"
+            "5e1b81d0ae0e015c4e2100fdc968b23a3109b239eb14921ea33e8789ef3c8feb"
         );
+
+        // The same auction on another chain or contract has another hash
+        let hash = calc_auction_hash(&data);
+        let data = AuctionData {
+            chain_id: 1,
+            ..data
+        };
+        assert_ne!(calc_auction_hash(&data), hash);
+        let data = AuctionData {
+            chain_id: 17000,
+            contract_address: vec![0; 20],
+            ..data
+        };
+        assert_ne!(calc_auction_hash(&data), hash);
+
+        // The auction rule is bound to the hash
+        let data = AuctionData {
+            contract_address: hex::decode("666fdd363f32e3b0efac35739aa9e2ee52a4b50e").unwrap(),
+            auction_rule: AuctionRule::LowestUniqueBid,
+            ..data
+        };
+        assert_ne!(calc_auction_hash(&data), hash);
+
+        // The payout terms are bound to the hash
+        let data = AuctionData {
+            auction_rule: AuctionRule::HighestBid,
+            fee_bps: 300,
+            ..data
+        };
+        assert_ne!(calc_auction_hash(&data), hash);
+    }
+
+    #[test]
+    #[should_panic(expected = "address must be 20 bytes")]
+    fn test_hash_auction_address_length() {
+        // Moving a byte between two packed addresses would keep the same preimage
+        calc_auction_hash(&AuctionData {
+            fee_recipient: vec![1; 21],
+            royalty_recipient: vec![2; 19],
+            ..AuctionData::test_default()
+        });
+    }
+
+    #[test]
+    fn test_clearing_price() {
+        let forward = AuctionDirection::Forward;
+        assert_eq!(
+            PricingRule::FirstPrice.clearing_price(forward, 10, Some(7), 0),
+            10
+        );
+        assert_eq!(
+            PricingRule::FirstPrice.clearing_price(forward, 10, Some(7), 8),
+            10
+        );
+        assert_eq!(
+            PricingRule::SecondPrice.clearing_price(forward, 10, Some(7), 0),
+            7
+        );
+        assert_eq!(
+            PricingRule::SecondPrice.clearing_price(forward, 10, None, 0),
+            0
+        );
+        assert_eq!(
+            PricingRule::SecondPrice.clearing_price(forward, 10, Some(7), 8),
+            8
+        );
+
+        let reverse = AuctionDirection::Reverse;
+        assert_eq!(
+            PricingRule::FirstPrice.clearing_price(reverse, 7, Some(10), 12),
+            7
+        );
+        assert_eq!(
+            PricingRule::SecondPrice.clearing_price(reverse, 7, Some(10), 12),
+            10
+        );
+        assert_eq!(
+            PricingRule::SecondPrice.clearing_price(reverse, 7, Some(10), 9),
+            9
+        );
+        assert_eq!(
+            PricingRule::SecondPrice.clearing_price(reverse, 7, None, 12),
+            12
+        );
+    }
+
+    #[test]
+    fn test_tie_break() {
+        let mut hash = [0; 32];
+        hash[31] = 5;
+        assert_eq!(TieBreak::EarliestBid.pick(3, &hash), 0);
+        assert_eq!(TieBreak::Random.pick(3, &hash), 2);
+        assert_eq!(TieBreak::Random.pick(1, &hash), 0);
+        assert_eq!(TieBreak::EarliestBid.rank(3, &hash), vec![0, 1, 2]);
+        assert_eq!(TieBreak::Random.rank(3, &hash), vec![2, 1, 0]);
     }
 
-    fn encrypt_bidder_amount(amount: &u128, pbk: &PublicKey) -> Vec<u8> {
-        ecies::encrypt(&pbk.serialize(), &amount.to_be_bytes())
+    fn encrypt_bidder_amount(
+        amount: &u128,
+        bidder_address: &[u8],
+        auction_id: &[u8],
+        pbk: &PublicKey,
+    ) -> Vec<u8> {
+        CipherScheme::Ecies
+            .cipher()
+            .encrypt(
+                &pbk.serialize(),
+                &encode_bid(*amount, bidder_address, auction_id),
+            )
             .expect("failed to encrypt bidder data")
     }
 
     fn get_key() -> (SecretKey, PublicKey) {
-        let pvk = SecretKey::parse_slice(&hex::decode(fs::read_to_string("private_encryption_key").unwrap()).unwrap())
-            .expect("fail to read private key");
+        let pvk = SecretKey::random(&mut OsRng);
         (pvk, PublicKey::from_secret_key(&pvk))
     }
 }
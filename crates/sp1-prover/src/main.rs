@@ -1,29 +1,72 @@
 #![no_main]
 
-use aligned_sp1_prover::{calc_auction_hash, decrypt_bidder_data, AuctionData};
-use ecies::SecretKey;
+use aligned_sp1_prover::{
+    aggregate_chunks, find_winner, rank_bids, AuctionData, AuctionPublicValues, BidChunk,
+    ProgramInput,
+};
+use ecies::{PublicKey, SecretKey};
+use sha2::{Digest, Sha256};
 
 sp1_zkvm::entrypoint!(main);
 
+/// Digest of the verification key of the chunk program, written by `make chunk-elf`
+const CHUNK_VK_DIGEST: [u32; 8] = include!(env!(
+    "CHUNK_VK_DIGEST_PATH",
+    "elf/chunk_vk_digest is missing, run `make chunk-elf` first"
+));
+
 /// Entrypoint for the zkVM program.
 pub fn main() {
-    let auction_data = sp1_zkvm::io::read::<AuctionData>();
-
-    let pvk = SecretKey::parse_slice(&sp1_zkvm::io::read::<Vec<u8>>())
-        .expect("missing private key to encode bidder data");
-
-    // Find the winner
-    let mut winner_addr = &vec![];
-    let mut winner_amount = 0;
-    for bidder in &auction_data.bidders {
-        let bidder_amount = decrypt_bidder_data(&pvk, bidder);
-        if winner_amount < bidder_amount {
-            winner_amount = bidder_amount;
-            winner_addr = &bidder.address;
-        }
+    // Auctions of the same owner are proven together, sharing one proof
+    let auctions = sp1_zkvm::io::read::<Vec<AuctionData>>();
+    for auction_data in &auctions {
+        auction_data.validate().expect("invalid auction");
     }
 
-    sp1_zkvm::io::commit(&calc_auction_hash(&auction_data));
-    sp1_zkvm::io::commit(winner_addr);
-    sp1_zkvm::io::commit(&winner_amount);
+    // Bind the proof to the encryption key published in each auction
+    let batch: Vec<AuctionPublicValues> = match sp1_zkvm::io::read::<ProgramInput>() {
+        ProgramInput::PrivateKey(pvk) => {
+            let pvk =
+                SecretKey::parse_slice(&pvk).expect("missing private key to encode bidder data");
+            let pbk = PublicKey::from_secret_key(&pvk);
+            auctions
+                .iter()
+                .map(|auction_data| {
+                    find_winner(auction_data, &pvk).public_values(auction_data, &pbk)
+                })
+                .collect()
+        }
+        ProgramInput::Chunks {
+            encryption_key,
+            chunks,
+        } => {
+            let pbk = PublicKey::parse(
+                encryption_key
+                    .as_slice()
+                    .try_into()
+                    .expect("invalid public key length"),
+            )
+            .expect("invalid public key");
+            assert_eq!(auctions.len(), chunks.len(), "missing chunks of an auction");
+            auctions
+                .iter()
+                .zip(chunks)
+                .map(|(auction_data, chunks)| {
+                    let chunks: Vec<BidChunk> = chunks
+                        .iter()
+                        .map(|public_values| {
+                            // Only the proofs of the chunk program are accepted
+                            let digest: [u8; 32] = Sha256::digest(public_values).into();
+                            sp1_zkvm::lib::verify::verify_sp1_proof(&CHUNK_VK_DIGEST, &digest);
+                            BidChunk::decode(public_values).expect("invalid chunk")
+                        })
+                        .collect();
+                    let bidder_amounts = aggregate_chunks(auction_data, &chunks, &pbk)
+                        .expect("chunks do not match the auction");
+                    rank_bids(auction_data, &bidder_amounts).public_values(auction_data, &pbk)
+                })
+                .collect()
+        }
+    };
+    sp1_zkvm::io::commit_slice(&AuctionPublicValues::encode_batch(&batch));
 }
@@ -0,0 +1,217 @@
+use crate::{keccak256, Bidder};
+
+/// Prefix of the leaves, so a leaf can not be passed off as an inner node
+const LEAF_PREFIX: u8 = 0;
+/// Prefix of the inner nodes
+const NODE_PREFIX: u8 = 1;
+
+/// Calculate the leaf of a bid in the Merkle tree of the bids
+///
+/// # Arguments
+///
+/// * `bidder`: encrypted bidder data
+///
+/// returns: [u8; 32] hash(0 || address || encrypted amount) in bytes
+pub fn bid_leaf(bidder: &Bidder) -> [u8; 32] {
+    keccak256(
+        &[
+            &[LEAF_PREFIX],
+            bidder.address.as_slice(),
+            bidder.encrypted_amount.as_slice(),
+        ]
+        .concat(),
+    )
+}
+
+/// Calculate the Merkle root of the bids. Pairs are hashed by position, so the root binds the
+/// order of the bids, and the last node of an odd level is promoted to the next level unchanged.
+///
+/// # Arguments
+///
+/// * `bidders`: encrypted bidder data in bid order
+///
+/// returns: [u8; 32] Merkle root, zero if there is no bid
+pub fn bids_root(bidders: &[Bidder]) -> [u8; 32] {
+    let mut nodes: Vec<[u8; 32]> = bidders.iter().map(bid_leaf).collect();
+    if nodes.is_empty() {
+        return [0; 32];
+    }
+    while nodes.len() > 1 {
+        nodes = next_level(&nodes);
+    }
+    nodes[0]
+}
+
+/// Generate the proof that a bid is included in the Merkle root of the bids
+///
+/// # Arguments
+///
+/// * `bidders`: encrypted bidder data in bid order
+/// * `index`: index of the bid
+///
+/// returns: Option<Vec<[u8; 32], Global>> Siblings from the leaf to the root, `None` if there is
+/// no bid at `index`
+pub fn inclusion_proof(bidders: &[Bidder], index: usize) -> Option<Vec<[u8; 32]>> {
+    if index >= bidders.len() {
+        return None;
+    }
+    let mut nodes: Vec<[u8; 32]> = bidders.iter().map(bid_leaf).collect();
+    let mut index = index;
+    let mut proof = vec![];
+    while nodes.len() > 1 {
+        // A promoted node has no sibling at this level
+        if let Some(sibling) = nodes.get(index ^ 1) {
+            proof.push(*sibling);
+        }
+        nodes = next_level(&nodes);
+        index /= 2;
+    }
+    Some(proof)
+}
+
+/// Verify that a leaf is included at an index in a Merkle root of the bids
+///
+/// # Arguments
+///
+/// * `root`: Merkle root of the bids, see `bids_root`
+/// * `leaf`: leaf of the bid, see `bid_leaf`
+/// * `index`: index of the bid, its bits give the side of the node at each level
+/// * `count`: number of bids, to skip the levels where the node is promoted
+/// * `proof`: siblings from the leaf to the root, see `inclusion_proof`
+///
+/// returns: bool `true` if the bid is included at `index`
+pub fn verify_inclusion(
+    root: &[u8; 32],
+    leaf: [u8; 32],
+    index: usize,
+    count: usize,
+    proof: &[[u8; 32]],
+) -> bool {
+    if index >= count {
+        return false;
+    }
+    let mut siblings = proof.iter();
+    let mut node = leaf;
+    let mut index = index;
+    let mut count = count;
+    while count > 1 {
+        // A promoted node has no sibling at this level
+        if index ^ 1 < count {
+            let Some(sibling) = siblings.next() else {
+                return false;
+            };
+            node = if index & 1 == 0 {
+                hash_pair(&node, sibling)
+            } else {
+                hash_pair(sibling, &node)
+            };
+        }
+        index /= 2;
+        count = count.div_ceil(2);
+    }
+    siblings.next().is_none() && node == *root
+}
+
+fn next_level(nodes: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    nodes
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => hash_pair(left, right),
+            [node] => *node,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    keccak256(&[&[NODE_PREFIX], left.as_slice(), right.as_slice()].concat())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::merkle::{bid_leaf, bids_root, hash_pair, inclusion_proof, verify_inclusion};
+    use crate::Bidder;
+
+    fn bidders(count: u8) -> Vec<Bidder> {
+        (0..count)
+            .map(|i| Bidder {
+                encrypted_amount: vec![i; 97],
+                address: vec![i; 20],
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_bids_root() {
+        assert_eq!(bids_root(&[]), [0; 32]);
+
+        let bidders = bidders(3);
+        assert_eq!(bids_root(&bidders[..1]), bid_leaf(&bidders[0]));
+        let left = hash_pair(&bid_leaf(&bidders[0]), &bid_leaf(&bidders[1]));
+        assert_eq!(bids_root(&bidders[..2]), left);
+        // The last bid of an odd level is promoted
+        assert_eq!(
+            bids_root(&bidders),
+            hash_pair(&left, &bid_leaf(&bidders[2]))
+        );
+    }
+
+    #[test]
+    fn test_inclusion_proof() {
+        for count in 1..=7 {
+            let bidders = bidders(count);
+            let root = bids_root(&bidders);
+            for (index, bidder) in bidders.iter().enumerate() {
+                let proof = inclusion_proof(&bidders, index).unwrap();
+                assert!(verify_inclusion(
+                    &root,
+                    bid_leaf(bidder),
+                    index,
+                    bidders.len(),
+                    &proof
+                ));
+            }
+            assert_eq!(inclusion_proof(&bidders, count as usize), None);
+        }
+    }
+
+    #[test]
+    fn test_inclusion_proof_tampered_bid() {
+        let bidders = bidders(5);
+        let root = bids_root(&bidders);
+        let proof = inclusion_proof(&bidders, 2).unwrap();
+        let tampered = Bidder {
+            encrypted_amount: vec![9; 97],
+            address: bidders[2].address.clone(),
+        };
+        assert!(!verify_inclusion(&root, bid_leaf(&tampered), 2, 5, &proof));
+        assert!(!verify_inclusion(
+            &root,
+            bid_leaf(&bidders[3]),
+            2,
+            5,
+            &proof
+        ));
+        // The proof is bound to the index of the bid
+        assert!(!verify_inclusion(
+            &root,
+            bid_leaf(&bidders[2]),
+            3,
+            5,
+            &proof
+        ));
+    }
+
+    #[test]
+    fn test_bids_root_order() {
+        // Swapping two bids changes the root, so the proven order is the on-chain order
+        let mut bidders = bidders(4);
+        let root = bids_root(&bidders);
+        bidders.swap(0, 1);
+        assert_ne!(bids_root(&bidders), root);
+        bidders.swap(0, 1);
+        bidders.swap(0, 2);
+        bidders.swap(1, 3);
+        assert_ne!(bids_root(&bidders), root);
+    }
+}
@@ -0,0 +1,545 @@
+use std::cmp::Reverse;
+
+use alloy_primitives::{Address, U256};
+use ecies::{PublicKey, SecretKey};
+
+use crate::{
+    bids_root, calc_auction_hash, calc_encryption_key_hash, decrypt_bidder_data, AuctionData,
+    AuctionDirection, AuctionPublicValues, AuctionRule, BidError, Payout, RevealedBid, TieBreak,
+    BPS_DENOMINATOR, PUBLIC_VALUES_VERSION,
+};
+
+/// Result of an auction, computed the same way inside and outside the zkVM
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct AuctionOutcome {
+    /// Addresses of the winners from the best ranked bid, empty if no valid bid reaches the reserve
+    /// price
+    pub winners: Vec<Vec<u8>>,
+    /// Amounts of the winning bids, in the order of `winners`
+    pub winner_amounts: Vec<u128>,
+    /// Uniform price paid by every winner, or paid to every winner in a procurement, 0 if there is
+    /// no winner
+    pub clearing_price: u128,
+    /// Indices of the bids excluded from the auction
+    pub rejected_bids: Vec<u32>,
+    /// Address and amount of every bid in bid order if the auction reveals the bids, no amount if
+    /// the bid can not be decrypted or is bound to another bidder or auction
+    pub revealed_bids: Vec<(Vec<u8>, Option<u128>)>,
+}
+
+impl AuctionOutcome {
+    /// Build the public values committed by the zkVM program for this outcome
+    ///
+    /// # Arguments
+    ///
+    /// * `auction_data`: data of the auction
+    /// * `pbk`: owner's public key
+    ///
+    /// returns: AuctionPublicValues Public values of the proof
+    pub fn public_values(
+        &self,
+        auction_data: &AuctionData,
+        pbk: &PublicKey,
+    ) -> AuctionPublicValues {
+        AuctionPublicValues {
+            version: PUBLIC_VALUES_VERSION,
+            auction_hash: calc_auction_hash(auction_data).into(),
+            encryption_key_hash: calc_encryption_key_hash(pbk).into(),
+            bids_root: bids_root(&auction_data.bidders).into(),
+            winners: self
+                .winners
+                .iter()
+                .map(|winner| Address::from_slice(winner))
+                .collect(),
+            winner_amounts: self.winner_amounts.clone(),
+            clearing_price: self.clearing_price,
+            tie_break: auction_data.tie_break as u8,
+            rejected_bids: self.rejected_bids.clone(),
+            revealed_bids: self
+                .revealed_bids
+                .iter()
+                .map(|(bidder, amount)| RevealedBid {
+                    bidder: Address::from_slice(bidder),
+                    amount: amount.unwrap_or(0),
+                    valid: amount.is_some(),
+                })
+                .collect(),
+            payout: self.payout(auction_data),
+        }
+    }
+
+    /// Split the proceeds of a sale, paid by every winner at the clearing price, between the
+    /// owner, the fee recipient and the royalty recipient. The fee and the royalty are rounded
+    /// down, the owner gets the remainder. A procurement has no proceeds.
+    ///
+    /// # Arguments
+    ///
+    /// * `auction_data`: data of the auction, see `AuctionData::validate`
+    ///
+    /// returns: Payout Amount paid to each recipient
+    pub fn payout(&self, auction_data: &AuctionData) -> Payout {
+        if auction_data.direction == AuctionDirection::Reverse {
+            return Payout::default();
+        }
+        let proceeds = U256::from(self.clearing_price) * U256::from(self.winners.len());
+        let fee_amount = proceeds * U256::from(auction_data.fee_bps) / U256::from(BPS_DENOMINATOR);
+        let royalty_amount =
+            proceeds * U256::from(auction_data.royalty_bps) / U256::from(BPS_DENOMINATOR);
+        Payout {
+            owner_amount: proceeds - fee_amount - royalty_amount,
+            fee_amount,
+            royalty_amount,
+        }
+    }
+}
+
+/// Find the winners of the auction, skipping the bids that can not be decrypted, are bound to
+/// another bidder or auction, or exceed the deposit of a sale. The `units` best ranked bids
+/// reaching the reserve price win, see `AuctionRule`.
+///
+/// # Arguments
+///
+/// * `auction_data`: data of the auction
+/// * `pvk`: owner's private key
+///
+/// returns: AuctionOutcome Winners, winning bids, clearing price and rejected bids
+pub fn find_winner(auction_data: &AuctionData, pvk: &SecretKey) -> AuctionOutcome {
+    let bidder_amounts: Vec<Result<u128, BidError>> = auction_data
+        .bidders
+        .iter()
+        .map(|bidder| {
+            decrypt_bidder_data(
+                auction_data.cipher_scheme.cipher(),
+                pvk,
+                bidder,
+                &auction_data.id,
+            )
+        })
+        .collect();
+    rank_bids(auction_data, &bidder_amounts)
+}
+
+/// Find the winners of the auction from bids that are already decrypted, e.g. by the chunk
+/// proofs, see `find_winner`
+///
+/// # Arguments
+///
+/// * `auction_data`: data of the auction
+/// * `bidder_amounts`: decrypted amount of every bid in bid order, or the reason it is invalid
+///
+/// returns: AuctionOutcome Winners, winning bids, clearing price and rejected bids
+pub fn rank_bids(
+    auction_data: &AuctionData,
+    bidder_amounts: &[Result<u128, BidError>],
+) -> AuctionOutcome {
+    let mut ranking: Vec<(u128, &Vec<u8>)> = vec![];
+    let mut rejected_bids: Vec<u32> = vec![];
+    let mut revealed_bids: Vec<(Vec<u8>, Option<u128>)> = vec![];
+    for (index, (bidder, bidder_amount)) in auction_data
+        .bidders
+        .iter()
+        .zip(bidder_amounts.iter().copied())
+        .enumerate()
+    {
+        if auction_data.reveal_bids {
+            revealed_bids.push((bidder.address.clone(), bidder_amount.ok()));
+        }
+        match bidder_amount {
+            // A bid of 0 never wins
+            Ok(0) => {}
+            // The winner of a sale can not pay more than the deposit
+            Ok(bidder_amount)
+                if auction_data.direction == AuctionDirection::Forward
+                    && bidder_amount > auction_data.deposit_price =>
+            {
+                rejected_bids.push(index as u32)
+            }
+            Ok(bidder_amount) => ranking.push((bidder_amount, &bidder.address)),
+            Err(_) => rejected_bids.push(index as u32),
+        }
+    }
+    let (winners, first_losing_amount) = match auction_data.auction_rule {
+        AuctionRule::HighestBid => best_bids(ranking, auction_data),
+        AuctionRule::LowestUniqueBid => lowest_unique_bids(ranking, auction_data),
+    };
+
+    // The winner closest to the losing bids sets the first price
+    let winning_amounts = winners.iter().map(|(amount, _)| *amount);
+    let last_winning_amount = match auction_data.direction {
+        AuctionDirection::Forward => winning_amounts.min(),
+        AuctionDirection::Reverse => winning_amounts.max(),
+    };
+    // No sale when no valid bid reaches the reserve price, including auctions without any bid
+    let Some(last_winning_amount) = last_winning_amount else {
+        return AuctionOutcome {
+            rejected_bids,
+            revealed_bids,
+            ..Default::default()
+        };
+    };
+
+    AuctionOutcome {
+        winners: winners
+            .iter()
+            .map(|(_, address)| address.to_vec())
+            .collect(),
+        winner_amounts: winners.iter().map(|(amount, _)| *amount).collect(),
+        clearing_price: auction_data.pricing_rule.clearing_price(
+            auction_data.direction,
+            last_winning_amount,
+            first_losing_amount,
+            auction_data.reserve_price,
+        ),
+        rejected_bids,
+        revealed_bids,
+    }
+}
+
+/// Rank the bids from the highest, or from the lowest in a procurement, bids tied at the same
+/// amount are ranked by the tie-break policy
+///
+/// returns: (Vec<(u128, &Vec<u8, Global>), Global>, Option<u128>) Winning bids and amount of the
+/// best ranked losing bid
+fn best_bids<'a>(
+    mut ranking: Vec<(u128, &'a Vec<u8>)>,
+    auction_data: &AuctionData,
+) -> (Vec<(u128, &'a Vec<u8>)>, Option<u128>) {
+    // Best bids first, the sort is stable so tied bids stay in bid order
+    match auction_data.direction {
+        AuctionDirection::Forward => ranking.sort_by_key(|(amount, _)| Reverse(*amount)),
+        AuctionDirection::Reverse => ranking.sort_by_key(|(amount, _)| *amount),
+    }
+    if auction_data.tie_break != TieBreak::EarliestBid {
+        break_ties(&mut ranking, auction_data);
+    }
+    let winners: Vec<_> = ranking
+        .iter()
+        .take(auction_data.units as usize)
+        .take_while(|(amount, _)| {
+            auction_data
+                .direction
+                .accepts(*amount, auction_data.reserve_price)
+        })
+        .copied()
+        .collect();
+    let first_losing_amount = ranking.get(winners.len()).map(|(amount, _)| *amount);
+    (winners, first_losing_amount)
+}
+
+/// Rank the amounts submitted by exactly one bidder from the lowest, always as a sale. Bids below
+/// the reserve price are ignored, so they can not make a bid reaching it non-unique.
+///
+/// returns: (Vec<(u128, &Vec<u8, Global>), Global>, Option<u128>) Winning bids, and no losing
+/// amount since the losing bids are not ranked after the winners
+fn lowest_unique_bids<'a>(
+    mut ranking: Vec<(u128, &'a Vec<u8>)>,
+    auction_data: &AuctionData,
+) -> (Vec<(u128, &'a Vec<u8>)>, Option<u128>) {
+    ranking.retain(|(amount, _)| *amount >= auction_data.reserve_price);
+    let mut unique_bids: Vec<_> = ranking
+        .iter()
+        .filter(|(amount, _)| ranking.iter().filter(|(other, _)| other == amount).count() == 1)
+        .copied()
+        .collect();
+    unique_bids.sort_by_key(|(amount, _)| *amount);
+    unique_bids.truncate(auction_data.units as usize);
+    (unique_bids, None)
+}
+
+/// Reorder each group of bids tied at the same amount with the tie-break policy of the auction
+fn break_ties(ranking: &mut [(u128, &Vec<u8>)], auction_data: &AuctionData) {
+    let auction_hash = calc_auction_hash(auction_data);
+    let mut start = 0;
+    while start < ranking.len() {
+        let tied = ranking[start..]
+            .iter()
+            .take_while(|(amount, _)| *amount == ranking[start].0)
+            .count();
+        let group = ranking[start..start + tied].to_vec();
+        for (slot, index) in auction_data
+            .tie_break
+            .rank(tied, &auction_hash)
+            .into_iter()
+            .enumerate()
+        {
+            ranking[start + slot] = group[index];
+        }
+        start += tied;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::U256;
+    use ecies::{PublicKey, SecretKey};
+    use rand::rngs::OsRng;
+
+    use crate::{
+        calc_auction_hash, encode_bid, find_winner, AuctionData, AuctionDataError,
+        AuctionDirection, AuctionRule, Bidder, CipherScheme, PricingRule, TieBreak,
+    };
+
+    fn auction_data(pbk: &PublicKey, amounts: &[u128]) -> AuctionData {
+        AuctionData {
+            bidders: amounts
+                .iter()
+                .enumerate()
+                .map(|(i, amount)| Bidder {
+                    encrypted_amount: CipherScheme::Ecies
+                        .cipher()
+                        .encrypt(
+                            &pbk.serialize(),
+                            &encode_bid(*amount, &[i as u8; 20], &[0; 32]),
+                        )
+                        .unwrap(),
+                    address: vec![i as u8; 20],
+                })
+                .collect(),
+            ..AuctionData::test_default()
+        }
+    }
+
+    #[test]
+    fn test_find_winner() {
+        let pvk = SecretKey::random(&mut OsRng);
+        let pbk = PublicKey::from_secret_key(&pvk);
+        let mut data = auction_data(&pbk, &[300, 900, 500]);
+
+        let outcome = find_winner(&data, &pvk);
+        assert_eq!(outcome.winners, vec![vec![1; 20]]);
+        assert_eq!(outcome.winner_amounts, vec![900]);
+        assert_eq!(outcome.clearing_price, 900);
+        assert!(outcome.rejected_bids.is_empty());
+
+        data.pricing_rule = PricingRule::SecondPrice;
+        assert_eq!(find_winner(&data, &pvk).clearing_price, 500);
+    }
+
+    #[test]
+    fn test_find_winner_multi_unit() {
+        let pvk = SecretKey::random(&mut OsRng);
+        let pbk = PublicKey::from_secret_key(&pvk);
+        let mut data = auction_data(&pbk, &[300, 900, 500, 700]);
+        data.units = 2;
+
+        let outcome = find_winner(&data, &pvk);
+        assert_eq!(outcome.winners, vec![vec![1; 20], vec![3; 20]]);
+        assert_eq!(outcome.winner_amounts, vec![900, 700]);
+        assert_eq!(outcome.clearing_price, 700);
+
+        data.pricing_rule = PricingRule::SecondPrice;
+        assert_eq!(find_winner(&data, &pvk).clearing_price, 500);
+
+        // Only the bids reaching the reserve price win
+        data.reserve_price = 800;
+        let outcome = find_winner(&data, &pvk);
+        assert_eq!(outcome.winners, vec![vec![1; 20]]);
+        assert_eq!(outcome.clearing_price, 800);
+
+        // Fewer bids than units
+        data.reserve_price = 0;
+        data.units = 10;
+        let outcome = find_winner(&data, &pvk);
+        assert_eq!(outcome.winners.len(), 4);
+        assert_eq!(outcome.clearing_price, 0);
+    }
+
+    #[test]
+    fn test_find_winner_tie_break() {
+        let pvk = SecretKey::random(&mut OsRng);
+        let pbk = PublicKey::from_secret_key(&pvk);
+        let mut data = auction_data(&pbk, &[300, 900, 500, 900, 900]);
+        data.pricing_rule = PricingRule::SecondPrice;
+
+        let outcome = find_winner(&data, &pvk);
+        assert_eq!(outcome.winners, vec![vec![1; 20]]);
+        assert_eq!(outcome.clearing_price, 900);
+        assert_eq!(outcome.public_values(&data, &pbk).tie_break, 0);
+
+        data.tie_break = TieBreak::Random;
+        let tied = [1, 3, 4];
+        let winner = tied[TieBreak::Random.pick(tied.len(), &calc_auction_hash(&data))];
+        let outcome = find_winner(&data, &pvk);
+        assert_eq!(outcome.winners, vec![vec![winner; 20]]);
+        assert_eq!(outcome.public_values(&data, &pbk).tie_break, 1);
+    }
+
+    #[test]
+    fn test_find_winner_reverse() {
+        let pvk = SecretKey::random(&mut OsRng);
+        let pbk = PublicKey::from_secret_key(&pvk);
+        let mut data = auction_data(&pbk, &[300, 900, 500, 700]);
+        data.direction = AuctionDirection::Reverse;
+        data.reserve_price = 800;
+
+        let outcome = find_winner(&data, &pvk);
+        assert_eq!(outcome.winners, vec![vec![0; 20]]);
+        assert_eq!(outcome.clearing_price, 300);
+
+        data.pricing_rule = PricingRule::SecondPrice;
+        assert_eq!(find_winner(&data, &pvk).clearing_price, 500);
+
+        data.units = 2;
+        let outcome = find_winner(&data, &pvk);
+        assert_eq!(outcome.winners, vec![vec![0; 20], vec![2; 20]]);
+        assert_eq!(outcome.winner_amounts, vec![300, 500]);
+        assert_eq!(outcome.clearing_price, 700);
+
+        // Only the bids under the maximum price win, and are paid at most the maximum price
+        data.reserve_price = 400;
+        let outcome = find_winner(&data, &pvk);
+        assert_eq!(outcome.winners, vec![vec![0; 20]]);
+        assert_eq!(outcome.clearing_price, 400);
+
+        data.reserve_price = 100;
+        assert!(find_winner(&data, &pvk).winners.is_empty());
+    }
+
+    #[test]
+    fn test_find_winner_lowest_unique_bid() {
+        let pvk = SecretKey::random(&mut OsRng);
+        let pbk = PublicKey::from_secret_key(&pvk);
+        let mut data = auction_data(&pbk, &[300, 100, 500, 100, 400]);
+        data.auction_rule = AuctionRule::LowestUniqueBid;
+
+        let outcome = find_winner(&data, &pvk);
+        assert_eq!(outcome.winners, vec![vec![0; 20]]);
+        assert_eq!(outcome.winner_amounts, vec![300]);
+        assert_eq!(outcome.clearing_price, 300);
+
+        data.units = 2;
+        let outcome = find_winner(&data, &pvk);
+        assert_eq!(outcome.winners, vec![vec![0; 20], vec![4; 20]]);
+        assert_eq!(outcome.winner_amounts, vec![300, 400]);
+        assert_eq!(outcome.clearing_price, 300);
+
+        // Bids below the reserve price are ignored
+        data.units = 1;
+        data.reserve_price = 350;
+        assert_eq!(find_winner(&data, &pvk).winners, vec![vec![4; 20]]);
+
+        // No amount is unique
+        let mut data = auction_data(&pbk, &[100, 100, 200, 200]);
+        data.auction_rule = AuctionRule::LowestUniqueBid;
+        let outcome = find_winner(&data, &pvk);
+        assert!(outcome.winners.is_empty());
+        assert_eq!(outcome.clearing_price, 0);
+    }
+
+    #[test]
+    fn test_find_winner_rejected_bids() {
+        let pvk = SecretKey::random(&mut OsRng);
+        let pbk = PublicKey::from_secret_key(&pvk);
+        let mut data = auction_data(&pbk, &[300, 900, 500]);
+        data.bidders[1].encrypted_amount = vec![1, 2, 3];
+
+        let outcome = find_winner(&data, &pvk);
+        assert_eq!(outcome.winners, vec![vec![2; 20]]);
+        assert_eq!(outcome.winner_amounts, vec![500]);
+        assert_eq!(outcome.rejected_bids, vec![1]);
+    }
+
+    #[test]
+    fn test_find_winner_above_deposit() {
+        let pvk = SecretKey::random(&mut OsRng);
+        let pbk = PublicKey::from_secret_key(&pvk);
+        let mut data = auction_data(&pbk, &[300, 1200, 500, 1000]);
+
+        let outcome = find_winner(&data, &pvk);
+        assert_eq!(outcome.winners, vec![vec![3; 20]]);
+        assert_eq!(outcome.winner_amounts, vec![1000]);
+        assert_eq!(outcome.rejected_bids, vec![1]);
+
+        data.pricing_rule = PricingRule::SecondPrice;
+        assert_eq!(find_winner(&data, &pvk).clearing_price, 500);
+
+        // The deposit does not cap a procurement
+        data.direction = AuctionDirection::Reverse;
+        data.reserve_price = 2000;
+        data.units = 4;
+        let outcome = find_winner(&data, &pvk);
+        assert_eq!(outcome.winners.len(), 4);
+        assert!(outcome.rejected_bids.is_empty());
+    }
+
+    #[test]
+    fn test_find_winner_reveal_bids() {
+        let pvk = SecretKey::random(&mut OsRng);
+        let pbk = PublicKey::from_secret_key(&pvk);
+        let mut data = auction_data(&pbk, &[300, 900, 500]);
+        assert!(find_winner(&data, &pvk).revealed_bids.is_empty());
+
+        data.reveal_bids = true;
+        data.bidders[2].encrypted_amount = vec![1, 2, 3];
+        let outcome = find_winner(&data, &pvk);
+        assert_eq!(
+            outcome.revealed_bids,
+            vec![
+                (vec![0; 20], Some(300)),
+                (vec![1; 20], Some(900)),
+                (vec![2; 20], None)
+            ]
+        );
+        assert_eq!(outcome.winners, vec![vec![1; 20]]);
+
+        let public_values = outcome.public_values(&data, &pbk);
+        assert_eq!(public_values.revealed_bids.len(), 3);
+        assert_eq!(public_values.revealed_bids[1].amount, 900);
+        assert!(public_values.revealed_bids[1].valid);
+        // An unreadable bid is not mistaken for a bid of 0
+        assert_eq!(public_values.revealed_bids[2].amount, 0);
+        assert!(!public_values.revealed_bids[2].valid);
+    }
+
+    #[test]
+    fn test_find_winner_no_sale() {
+        let pvk = SecretKey::random(&mut OsRng);
+        let pbk = PublicKey::from_secret_key(&pvk);
+        let mut data = auction_data(&pbk, &[300, 900, 500]);
+        data.reserve_price = 1000;
+
+        let outcome = find_winner(&data, &pvk);
+        assert!(outcome.winners.is_empty());
+        assert_eq!(outcome.clearing_price, 0);
+        assert!(!outcome.public_values(&data, &pbk).has_winner());
+
+        let data = auction_data(&pbk, &[]);
+        assert_eq!(find_winner(&data, &pvk), Default::default());
+    }
+
+    #[test]
+    fn test_payout() {
+        let pvk = SecretKey::random(&mut OsRng);
+        let pbk = PublicKey::from_secret_key(&pvk);
+        let mut data = auction_data(&pbk, &[300, 999, 500, 999]);
+        data.units = 2;
+        data.fee_bps = 250;
+        data.royalty_bps = 1000;
+
+        // Both winners pay 999, the fee and the royalty are rounded down
+        let payout = find_winner(&data, &pvk).public_values(&data, &pbk).payout;
+        assert_eq!(payout.fee_amount, U256::from(49));
+        assert_eq!(payout.royalty_amount, U256::from(199));
+        assert_eq!(payout.owner_amount, U256::from(1998 - 49 - 199));
+
+        // Nothing is paid without a sale or in a procurement
+        data.reserve_price = 1000;
+        assert_eq!(find_winner(&data, &pvk).payout(&data), Default::default());
+        data.direction = AuctionDirection::Reverse;
+        assert_eq!(find_winner(&data, &pvk).payout(&data), Default::default());
+    }
+
+    #[test]
+    fn test_payout_above_proceeds() {
+        let pvk = SecretKey::random(&mut OsRng);
+        let pbk = PublicKey::from_secret_key(&pvk);
+        let mut data = auction_data(&pbk, &[300]);
+        data.fee_bps = 9000;
+        data.royalty_bps = 1000;
+        assert_eq!(data.validate(), Ok(()));
+
+        // The owner amount would underflow
+        data.royalty_bps = 1001;
+        assert_eq!(data.validate(), Err(AuctionDataError::PayoutAboveProceeds));
+    }
+}
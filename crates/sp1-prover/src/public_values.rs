@@ -0,0 +1,278 @@
+use std::fmt::{Display, Formatter};
+
+use alloy_sol_types::{sol, SolValue};
+
+/// Version of the public values layout, bumped on every change of `AuctionPublicValues`
+pub const PUBLIC_VALUES_VERSION: u8 = 8;
+
+sol! {
+    /// Bid revealed once the auction ends
+    #[derive(Debug, PartialEq, Eq)]
+    struct RevealedBid {
+        /// Address of the bidder
+        address bidder;
+        /// Decrypted amount, 0 if the bid is not valid
+        uint128 amount;
+        /// Whether the bid was decrypted and bound to the bidder and the auction, so an unreadable
+        /// bid is not mistaken for a bid of 0
+        bool valid;
+    }
+
+    /// Split of the proceeds of a sale, derived from the clearing price and the basis points of
+    /// the auction
+    #[derive(Debug, Default, PartialEq, Eq)]
+    struct Payout {
+        /// Amount paid to the owner of the auction
+        uint256 owner_amount;
+        /// Amount paid to the fee recipient of the marketplace
+        uint256 fee_amount;
+        /// Amount paid to the royalty recipient of the NFTs
+        uint256 royalty_amount;
+    }
+
+    /// Public values of one auction, the zkVM program commits one per proven auction so the
+    /// contract can decode them with `abi.decode(publicInput, (PublicValues[]))`
+    #[derive(Debug, PartialEq, Eq)]
+    struct AuctionPublicValues {
+        /// Layout version, equal to `PUBLIC_VALUES_VERSION`
+        uint8 version;
+        /// Hash of the auction data, see `calc_auction_hash`
+        bytes32 auction_hash;
+        /// Hash of the owner's public encryption key, see `calc_encryption_key_hash`
+        bytes32 encryption_key_hash;
+        /// Merkle root of the bids, see `bids_root`
+        bytes32 bids_root;
+        /// Addresses of the winners from the best ranked bid, empty if there is no winner
+        address[] winners;
+        /// Amounts of the winning bids, in the order of `winners`
+        uint128[] winner_amounts;
+        /// Uniform price paid by every winner
+        uint128 clearing_price;
+        /// Tie-break policy applied to the bids tied at the highest amount, see `TieBreak`
+        uint8 tie_break;
+        /// Indices of the bids excluded from the auction
+        uint32[] rejected_bids;
+        /// Every bid in bid order if the auction reveals the bids, empty otherwise
+        RevealedBid[] revealed_bids;
+        /// Split of the proceeds, all zero if there is no sale or in a procurement
+        Payout payout;
+    }
+}
+
+/// Error returned when the public values can not be decoded
+#[derive(Debug)]
+pub enum PublicValuesError {
+    /// The data is not a valid ABI encoding of `AuctionPublicValues`
+    Decode(alloy_sol_types::Error),
+    /// The data was produced with another layout version
+    UnsupportedVersion(u8),
+}
+
+impl Display for PublicValuesError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PublicValuesError::Decode(e) => write!(f, "failed to decode public values: {}", e),
+            PublicValuesError::UnsupportedVersion(version) => {
+                write!(f, "unsupported public values version: {}", version)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PublicValuesError {}
+
+impl AuctionPublicValues {
+    /// Encode the public values of a single auction
+    ///
+    /// returns: Vec<u8, Global> ABI-encoded public values
+    pub fn encode(&self) -> Vec<u8> {
+        self.abi_encode()
+    }
+
+    /// Decode the public values of a single auction
+    ///
+    /// # Arguments
+    ///
+    /// * `data`: ABI-encoded public values
+    ///
+    /// returns: Result<AuctionPublicValues, PublicValuesError> Decoded public values
+    pub fn decode(data: &[u8]) -> Result<Self, PublicValuesError> {
+        let public_values =
+            <Self as SolValue>::abi_decode(data, true).map_err(PublicValuesError::Decode)?;
+        if public_values.version != PUBLIC_VALUES_VERSION {
+            return Err(PublicValuesError::UnsupportedVersion(public_values.version));
+        }
+        Ok(public_values)
+    }
+
+    /// Encode the public values of a batch of auctions to the bytes committed by the zkVM program
+    ///
+    /// # Arguments
+    ///
+    /// * `batch`: public values of each auction, in proving order
+    ///
+    /// returns: Vec<u8, Global> ABI-encoded array of public values
+    pub fn encode_batch(batch: &[AuctionPublicValues]) -> Vec<u8> {
+        batch.abi_encode()
+    }
+
+    /// Decode the public values of a batch of auctions committed by the zkVM program
+    ///
+    /// # Arguments
+    ///
+    /// * `data`: ABI-encoded array of public values
+    ///
+    /// returns: Result<Vec<AuctionPublicValues, Global>, PublicValuesError> Decoded public values
+    /// of each auction, in proving order
+    pub fn decode_batch(data: &[u8]) -> Result<Vec<Self>, PublicValuesError> {
+        let batch =
+            <Vec<Self> as SolValue>::abi_decode(data, true).map_err(PublicValuesError::Decode)?;
+        if let Some(public_values) = batch
+            .iter()
+            .find(|public_values| public_values.version != PUBLIC_VALUES_VERSION)
+        {
+            return Err(PublicValuesError::UnsupportedVersion(public_values.version));
+        }
+        Ok(batch)
+    }
+
+    /// Whether the auction has at least one winner
+    pub fn has_winner(&self) -> bool {
+        !self.winners.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{Address, FixedBytes, U256};
+
+    use crate::public_values::{
+        AuctionPublicValues, Payout, PublicValuesError, RevealedBid, PUBLIC_VALUES_VERSION,
+    };
+
+    fn public_values() -> AuctionPublicValues {
+        AuctionPublicValues {
+            version: PUBLIC_VALUES_VERSION,
+            auction_hash: FixedBytes([1; 32]),
+            encryption_key_hash: FixedBytes([2; 32]),
+            bids_root: FixedBytes([5; 32]),
+            winners: vec![Address::repeat_byte(3)],
+            winner_amounts: vec![900],
+            clearing_price: 800,
+            tie_break: 1,
+            rejected_bids: vec![4, 7],
+            revealed_bids: vec![RevealedBid {
+                bidder: Address::repeat_byte(3),
+                amount: 900,
+                valid: true,
+            }],
+            payout: Payout {
+                owner_amount: U256::from(700),
+                fee_amount: U256::from(60),
+                royalty_amount: U256::from(40),
+            },
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let public_values = public_values();
+        let decoded = AuctionPublicValues::decode(&public_values.encode()).unwrap();
+        assert_eq!(decoded, public_values);
+        assert!(decoded.has_winner());
+    }
+
+    #[test]
+    fn test_layout() {
+        let encoded = public_values().encode();
+        let word = |i: usize| hex::encode(&encoded[32 * i..32 * (i + 1)]);
+
+        assert_eq!(encoded.len(), 32 * 25);
+        // offset of the struct, which is dynamic because of its arrays
+        assert_eq!(word(0), format!("{:064x}", 0x20));
+        assert_eq!(word(1), format!("{:064x}", PUBLIC_VALUES_VERSION));
+        assert_eq!(word(2), "01".repeat(32));
+        assert_eq!(word(3), "02".repeat(32));
+        assert_eq!(word(4), "05".repeat(32));
+        // offsets of `winners` and `winner_amounts` inside the struct
+        assert_eq!(word(5), format!("{:064x}", 32 * 13));
+        assert_eq!(word(6), format!("{:064x}", 32 * 15));
+        assert_eq!(word(7), format!("{:064x}", 800));
+        assert_eq!(word(8), format!("{:064x}", 1));
+        // offsets of `rejected_bids` and `revealed_bids` inside the struct
+        assert_eq!(word(9), format!("{:064x}", 32 * 17));
+        assert_eq!(word(10), format!("{:064x}", 32 * 20));
+        // the payout is a static tuple, stored inline
+        assert_eq!(word(11), format!("{:064x}", 700));
+        assert_eq!(word(12), format!("{:064x}", 60));
+        assert_eq!(word(13), format!("{:064x}", 40));
+        // length and items of each array
+        assert_eq!(word(14), format!("{:064x}", 1));
+        assert_eq!(word(15), format!("{}{}", "00".repeat(12), "03".repeat(20)));
+        assert_eq!(word(16), format!("{:064x}", 1));
+        assert_eq!(word(17), format!("{:064x}", 900));
+        assert_eq!(word(18), format!("{:064x}", 2));
+        assert_eq!(word(19), format!("{:064x}", 4));
+        assert_eq!(word(20), format!("{:064x}", 7));
+        // revealed bids are static tuples, stored inline
+        assert_eq!(word(21), format!("{:064x}", 1));
+        assert_eq!(word(22), format!("{}{}", "00".repeat(12), "03".repeat(20)));
+        assert_eq!(word(23), format!("{:064x}", 900));
+        assert_eq!(word(24), format!("{:064x}", 1));
+    }
+
+    #[test]
+    fn test_no_winner() {
+        let public_values = AuctionPublicValues {
+            winners: vec![],
+            winner_amounts: vec![],
+            ..public_values()
+        };
+        assert!(!AuctionPublicValues::decode(&public_values.encode())
+            .unwrap()
+            .has_winner());
+    }
+
+    #[test]
+    fn test_batch_round_trip() {
+        let batch = vec![
+            public_values(),
+            AuctionPublicValues {
+                auction_hash: FixedBytes([6; 32]),
+                winners: vec![],
+                winner_amounts: vec![],
+                ..public_values()
+            },
+        ];
+        let encoded = AuctionPublicValues::encode_batch(&batch);
+        // offset and length of the array
+        assert_eq!(hex::encode(&encoded[..32]), format!("{:064x}", 0x20));
+        assert_eq!(hex::encode(&encoded[32..64]), format!("{:064x}", 2));
+        assert_eq!(AuctionPublicValues::decode_batch(&encoded).unwrap(), batch);
+        assert!(AuctionPublicValues::decode_batch(&[]).is_err());
+
+        let batch = vec![
+            public_values(),
+            AuctionPublicValues {
+                version: PUBLIC_VALUES_VERSION + 1,
+                ..public_values()
+            },
+        ];
+        assert!(matches!(
+            AuctionPublicValues::decode_batch(&AuctionPublicValues::encode_batch(&batch)),
+            Err(PublicValuesError::UnsupportedVersion(_))
+        ));
+    }
+
+    #[test]
+    fn test_unsupported_version() {
+        let public_values = AuctionPublicValues {
+            version: PUBLIC_VALUES_VERSION + 1,
+            ..public_values()
+        };
+        assert!(matches!(
+            AuctionPublicValues::decode(&public_values.encode()),
+            Err(PublicValuesError::UnsupportedVersion(_))
+        ));
+    }
+}